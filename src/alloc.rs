@@ -0,0 +1,88 @@
+//! Allocation counting for `--profile-alloc`
+//!
+//! The counting allocator is only installed when building with the `profile-alloc` feature, so
+//! normal builds use the system allocator without any overhead and always report zero.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "profile-alloc")]
+pub(super) struct CountingAllocator;
+
+#[cfg(feature = "profile-alloc")]
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        std::alloc::System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: std::alloc::Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+
+        std::alloc::System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Whether allocations are actually being counted in this build
+pub(super) const ENABLED: bool = cfg!(feature = "profile-alloc");
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub(super) struct AllocStats {
+    /// The number of allocations, including reallocations
+    pub(super) allocations: usize,
+
+    /// The total number of bytes requested
+    pub(super) bytes: usize,
+}
+
+impl AllocStats {
+    /// The allocations that happened between `earlier` and `self`
+    pub(super) fn since(&self, earlier: &AllocStats) -> AllocStats {
+        AllocStats {
+            allocations: self.allocations - earlier.allocations,
+            bytes: self.bytes - earlier.bytes,
+        }
+    }
+}
+
+/// Take a snapshot of the allocations counted so far
+pub(super) fn snapshot() -> AllocStats {
+    AllocStats {
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_compute_allocations_since_snapshot() {
+        let earlier = AllocStats {
+            allocations: 3,
+            bytes: 128,
+        };
+        let now = AllocStats {
+            allocations: 10,
+            bytes: 1152,
+        };
+
+        assert_eq!(
+            now.since(&earlier),
+            AllocStats {
+                allocations: 7,
+                bytes: 1024
+            }
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! Tar archive inputs for `--archive`
+//!
+//! Reading archives is only built with the `archive` feature. Inputs are small, so every entry is
+//! read in memory up front.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Whether tar archives can be read in this build
+pub(super) const ENABLED: bool = cfg!(feature = "archive");
+
+/// Read the content of every file entry of the archive at `path`, keyed by its path in the archive
+#[cfg(feature = "archive")]
+pub(super) fn read_entries(path: &Path) -> io::Result<HashMap<PathBuf, String>> {
+    use std::io::Read;
+
+    let mut archive = tar::Archive::new(std::fs::File::open(path)?);
+    let mut entries = HashMap::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let entry_path = entry.path()?.into_owned();
+
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+
+        entries.insert(entry_path, content);
+    }
+
+    Ok(entries)
+}
+
+/// Read the content of every file entry of the archive at `path`, keyed by its path in the archive
+#[cfg(not(feature = "archive"))]
+pub(super) fn read_entries(_path: &Path) -> io::Result<HashMap<PathBuf, String>> {
+    Err(io::Error::other("built without the archive feature"))
+}
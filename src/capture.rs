@@ -0,0 +1,112 @@
+//! Stderr capture for `test --strict`
+//!
+//! Solvers print with `eprintln!` and friends, which write straight to the process stderr, so the
+//! file descriptor itself is redirected to a pipe while a test runs. This is only supported on
+//! unix, other platforms report the capture as unsupported.
+
+use std::io;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+
+/// The real stderr while a capture is running, -1 otherwise
+static REAL_STDERR: AtomicI32 = AtomicI32::new(-1);
+
+/// Only one capture can redirect the process stderr at a time
+static CAPTURE: Mutex<()> = Mutex::new(());
+
+/// Run `f`, returning what was written to stderr meanwhile along with its result
+#[cfg(unix)]
+pub(super) fn stderr<T>(f: impl FnOnce() -> T) -> io::Result<(T, String)> {
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::unix::io::FromRawFd;
+
+    let _guard = CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let [read_fd, write_fd] = fds;
+
+    let real_stderr = unsafe { libc::dup(libc::STDERR_FILENO) };
+    if real_stderr < 0 || unsafe { libc::dup2(write_fd, libc::STDERR_FILENO) } < 0 {
+        let e = io::Error::last_os_error();
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+            if real_stderr >= 0 {
+                libc::close(real_stderr);
+            }
+        }
+        return Err(e);
+    }
+    unsafe { libc::close(write_fd) };
+    REAL_STDERR.store(real_stderr, Ordering::SeqCst);
+
+    // Drain the pipe as we go, a chatty solver would otherwise block once it is full
+    let mut pipe = unsafe { File::from_raw_fd(read_fd) };
+    let reader = std::thread::spawn(move || {
+        let mut captured = Vec::new();
+        pipe.read_to_end(&mut captured).map(|_| captured)
+    });
+
+    let result = f();
+
+    // Putting the real stderr back closes the last write end of the pipe, ending the reader
+    REAL_STDERR.store(-1, Ordering::SeqCst);
+    let restored = unsafe { libc::dup2(real_stderr, libc::STDERR_FILENO) };
+    unsafe { libc::close(real_stderr) };
+    if restored < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let captured = reader
+        .join()
+        .map_err(|_| io::Error::other("the stderr reader panicked"))??;
+
+    Ok((result, String::from_utf8_lossy(&captured).into_owned()))
+}
+
+#[cfg(not(unix))]
+pub(super) fn stderr<T>(_f: impl FnOnce() -> T) -> io::Result<(T, String)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "stderr can only be captured on unix",
+    ))
+}
+
+/// Write `text` to the real stderr, even while a capture is running. This is meant for output
+/// the user asked for, like progress reports, which should not fail a strict test.
+pub(super) fn write_uncaptured(text: &str) {
+    #[cfg(unix)]
+    {
+        let fd = REAL_STDERR.load(Ordering::SeqCst);
+        if fd >= 0 {
+            // Losing a progress dot is not worth failing the solver over
+            unsafe { libc::write(fd, text.as_ptr().cast(), text.len()) };
+            return;
+        }
+    }
+
+    eprint!("{}", text);
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn should_capture_writes_to_stderr() {
+        let (result, captured) = stderr(|| {
+            // `eprintln!` is captured by the test harness, write to the stream directly instead
+            io::stderr().write_all(b"debug output\n").unwrap();
+            42
+        })
+        .unwrap();
+
+        assert_eq!(result, 42);
+        assert_eq!(captured, "debug output\n");
+    }
+}
@@ -0,0 +1,20 @@
+//! Clipboard access for `--from-clipboard`
+//!
+//! The clipboard bindings are only built with the `clipboard` feature, since they depend on the
+//! desktop environment of the platform.
+
+/// Whether the clipboard can be read in this build
+pub(super) const ENABLED: bool = cfg!(feature = "clipboard");
+
+/// Read the text currently held by the system clipboard
+#[cfg(feature = "clipboard")]
+pub(super) fn read_text() -> Result<String, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    clipboard.get_text().map_err(|e| e.to_string())
+}
+
+/// Read the text currently held by the system clipboard
+#[cfg(not(feature = "clipboard"))]
+pub(super) fn read_text() -> Result<String, String> {
+    Err("built without the clipboard feature".to_string())
+}
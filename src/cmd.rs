@@ -1,13 +1,28 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
 use std::string::String;
 use std::vec::Vec;
 
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::process;
 
-use std::time;
+use std::time::{self, Duration};
 
+use crate::alloc;
+use crate::archive;
+use crate::capture;
+use crate::clipboard;
 use crate::day;
+use crate::report::{self, ReportRow};
+use crate::watch;
+
+const REPORT_PATH: &str = "REPORT.md";
+
+/// Short forms of the commands, as `(alias, command)`
+const ALIASES: &[(&str, &str)] = &[("s", "solve"), ("t", "test")];
 
 #[derive(Debug)]
 pub(super) enum ParsePathError {
@@ -17,6 +32,20 @@ pub(super) enum ParsePathError {
     InvalidIndex(String, std::num::ParseIntError),
 }
 
+impl fmt::Display for ParsePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePathError::Empty => write!(f, "empty path"),
+            ParsePathError::InvalidPath(path) => write!(f, "invalid path {:?}", path),
+            ParsePathError::InvalidIndex(fragment, e) => {
+                write!(f, "invalid index in `{}`: {}", fragment, e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParsePathError {}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 struct ArgPathFragment {
     prefix: String,
@@ -29,16 +58,15 @@ impl ArgPathFragment {
             return Err(ParsePathError::Empty);
         }
 
-        let (prefix, index) = match s.find(|c: char| c.is_ascii_digit()) {
-            Some(idx) => {
-                let (prefix, index) = s.split_at(idx);
-                let index = index
+        // Only a trailing run of digits is an index, digits elsewhere belong to the prefix
+        let prefix = s.trim_end_matches(|c: char| c.is_ascii_digit());
+        let index = match &s[prefix.len()..] {
+            "" => None,
+            index => Some(
+                index
                     .parse::<usize>()
-                    .map_err(|e| ParsePathError::InvalidIndex(s.into(), e))?;
-
-                (prefix, Some(index))
-            }
-            None => (s, None),
+                    .map_err(|e| ParsePathError::InvalidIndex(s.into(), e))?,
+            ),
         };
 
         Ok(ArgPathFragment {
@@ -48,6 +76,13 @@ impl ArgPathFragment {
     }
 }
 
+/// Extension of the files holding the expected answer of an additional test file, e.g.
+/// `day16.part1.test2.expected` for `day16.part1.test2.txt`
+const EXPECTED_EXTENSION: &str = "expected";
+
+/// Separators accepted between path fragments, so that Windows-style paths work everywhere
+const PATH_SEPARATORS: &[char] = &['/', '\\'];
+
 #[derive(Debug, Clone)]
 struct ArgPath {
     value: String,
@@ -61,7 +96,7 @@ impl ArgPath {
         }
 
         let fragments = s
-            .split('/')
+            .split(PATH_SEPARATORS)
             .map(ArgPathFragment::parse)
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
@@ -72,10 +107,12 @@ impl ArgPath {
     }
 
     fn parse_path<P: AsRef<Path>>(path: P) -> std::result::Result<Self, ParsePathError> {
+        // `Path::file_name` only knows about the separators of the current platform
         let file_name = path
             .as_ref()
-            .file_name()
-            .and_then(|f| f.to_str())
+            .to_str()
+            .and_then(|p| p.rsplit(PATH_SEPARATORS).next())
+            .filter(|f| !f.is_empty())
             .ok_or(ParsePathError::InvalidPath(PathBuf::from(path.as_ref())))?;
 
         let mut file_parts: Vec<_> = file_name.split('.').collect();
@@ -94,6 +131,11 @@ impl ArgPath {
         })
     }
 
+    /// Whether this path designates every available day
+    fn is_all(&self) -> bool {
+        self.value == "all"
+    }
+
     fn fragment(&self, prefix: &str) -> Option<&ArgPathFragment> {
         self.fragments.iter().find(|&f| f.prefix == prefix)
     }
@@ -104,9 +146,7 @@ impl ArgPath {
 
     fn disjoint(&self, other: &ArgPath) -> Option<&ArgPathFragment> {
         for i in 0..self.fragments.len() {
-            if i >= other.fragments.len() {
-                return Some(&self.fragments[i]);
-            } else if self.fragments[i] != other.fragments[i] {
+            if i >= other.fragments.len() || self.fragments[i] != other.fragments[i] {
                 return Some(&self.fragments[i]);
             }
         }
@@ -123,9 +163,84 @@ impl FromStr for ArgPath {
     }
 }
 
+/// How answers are printed by the solve command
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum OutputFormat {
+    /// A human readable line per answer
+    Text,
+
+    /// `DAYn_PARTm=answer` lines that can be evaluated by a shell
+    Env,
+
+    /// A JSON object per day, holding the answer of each part
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "env" => Ok(OutputFormat::Env),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(Error::InvalidFormat(s.to_string())),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(super) struct CommonArgs {
     path: ArgPath,
+
+    /// Fail a test if its solver writes anything to stderr
+    strict: bool,
+
+    /// Only print how long the solver took, not its answer. This is only taken by `solve`, the
+    /// other commands are all about checking the answers.
+    time_only: bool,
+
+    /// Print the report instead of writing it to a file
+    stdout: bool,
+
+    /// Report the allocations made by each solve
+    profile_alloc: bool,
+
+    /// Read the input from the system clipboard instead of the input files
+    from_clipboard: bool,
+
+    /// Read the input files from this tar archive instead of the input directory
+    archive: Option<PathBuf>,
+
+    /// How answers are printed
+    format: OutputFormat,
+
+    /// Run the tests again whenever the source of the day changes
+    watch: bool,
+
+    /// Give up on a solver that does not answer within this budget
+    timeout: Option<Duration>,
+
+    /// Append the answers to this file, with a timestamp
+    log: Option<PathBuf>,
+
+    /// Print numeric answers with their digits grouped by thousands
+    group_digits: bool,
+
+    /// Only run this part, both parts run otherwise
+    part: Option<usize>,
+
+    /// Label each answer as coming from the sample or the real input
+    auto_test: bool,
+
+    /// Also run the additional test files of a day, e.g. `day16.part1.test2.txt`
+    all_files: bool,
+
+    /// Run against this file rather than the ones found in the input directory
+    input: Option<PathBuf>,
+
+    /// Options forwarded to the solvers
+    options: day::Options,
 }
 
 #[derive(Debug)]
@@ -135,18 +250,130 @@ pub(super) enum Error {
 
     InvalidCommand(String),
     InvalidPath(ParsePathError),
+    InvalidFlag(String),
+    MissingFlagValue(String),
+    InvalidFormat(String),
+    InvalidTimeout(String),
+    InvalidSkipHeader(String),
+    InvalidDays(String),
+    InvalidWindow(String),
+    InvalidDebugTopic(String),
+    InvalidPart(String),
+
+    FeatureDisabled(&'static str),
+
+    Clipboard(String),
+    Watch(String),
 
     ResolvePath(PathBuf),
 
     ReadInputDirectory(PathBuf, std::io::Error),
+    ReadArchive(PathBuf, std::io::Error),
+    MissingArchiveEntry(String),
+    WriteReport(PathBuf, std::io::Error),
+    WriteLog(PathBuf, std::io::Error),
+
+    Solver(PathBuf, day::SolverError),
 
-    SolverError(PathBuf, day::SolverError),
+    CaptureStderr(std::io::Error),
+
+    PlaceholderTests(usize),
+    FailedTests(usize),
+    WrongAnswers(usize),
+
+    NoInputFound(String),
+    AmbiguousInput(Vec<PathBuf>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingCommand => write!(f, "missing command"),
+            Error::MissingPath(cmd) => write!(f, "missing input path for command `{}`", cmd),
+            Error::InvalidCommand(cmd) => write!(f, "invalid command `{}`", cmd),
+            Error::InvalidPath(e) => write!(f, "invalid input path: {}", e),
+            Error::InvalidFlag(flag) => write!(f, "invalid flag `{}`", flag),
+            Error::MissingFlagValue(flag) => write!(f, "missing value for flag `{}`", flag),
+            Error::InvalidFormat(format) => write!(f, "invalid format `{}`", format),
+            Error::InvalidTimeout(value) => write!(f, "invalid timeout `{}`", value),
+            Error::InvalidSkipHeader(value) => {
+                write!(f, "invalid number of header lines `{}`", value)
+            }
+            Error::InvalidDays(value) => write!(f, "invalid number of days `{}`", value),
+            Error::InvalidWindow(value) => write!(f, "invalid window size `{}`", value),
+            Error::InvalidDebugTopic(topic) => {
+                write!(f, "invalid debug topic `{}`, expected `registry`", topic)
+            }
+            Error::InvalidPart(value) => write!(f, "invalid part `{}`", value),
+            Error::FeatureDisabled(feature) => {
+                write!(
+                    f,
+                    "this build was compiled without the `{}` feature",
+                    feature
+                )
+            }
+            Error::Clipboard(e) => write!(f, "could not read the clipboard: {}", e),
+            Error::Watch(e) => write!(f, "could not watch inputs: {}", e),
+            Error::ResolvePath(path) => write!(f, "could not resolve input path {:?}", path),
+            Error::ReadInputDirectory(path, e) => {
+                write!(f, "could not read inputs dir {:?}: {}", path, e)
+            }
+            Error::ReadArchive(path, e) => write!(f, "could not read archive {:?}: {}", path, e),
+            Error::MissingArchiveEntry(entry) => {
+                write!(f, "missing archive entry `{}`", entry)
+            }
+            Error::WriteReport(path, e) => write!(f, "could not write report {:?}: {}", path, e),
+            Error::WriteLog(path, e) => write!(f, "could not write log {:?}: {}", path, e),
+            Error::Solver(path, e) => write!(f, "solver failed on {:?}: {}", path, e),
+            Error::CaptureStderr(e) => write!(f, "could not capture stderr: {}", e),
+            Error::PlaceholderTests(count) => {
+                write!(f, "{} test parts have no expectation", count)
+            }
+            Error::FailedTests(count) => write!(f, "{} test files failed", count),
+            Error::WrongAnswers(count) => {
+                write!(f, "{} answers do not match the real expectations", count)
+            }
+            Error::NoInputFound(path) => write!(f, "could not find any input files for {}", path),
+            Error::AmbiguousInput(files) => {
+                write!(f, "several input files match the same part: {:?}", files)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug)]
+pub(super) struct ListArgs {
+    /// Print the catalog as JSON instead of one day per line
+    json: bool,
+}
+
+/// What the `debug` command prints, meant for developers only
+#[derive(Debug, PartialEq)]
+pub(super) enum DebugTopic {
+    /// The day, module and name of every registered solver
+    Registry,
+}
+
+#[derive(Debug)]
+pub(super) struct DoctorArgs {
+    /// Check that every implemented part has a test expectation
+    tests: bool,
+
+    /// Fail if any check found a problem
+    strict: bool,
 }
 
 #[derive(Debug)]
 pub(super) enum Command {
     Solve(CommonArgs),
     Test(CommonArgs),
+    Report(CommonArgs),
+    Verify(CommonArgs),
+    List(ListArgs),
+    Doctor(DoctorArgs),
+    Debug(DebugTopic),
 }
 
 pub(super) type Result<T> = std::result::Result<T, Error>;
@@ -163,13 +390,95 @@ fn read_input_files<P: AsRef<Path>>(path: P) -> Result<Vec<PathBuf>> {
         let path = entry.path();
 
         if path.is_file() {
-            input_files.push(path.into())
+            input_files.push(path)
         }
     }
 
     Ok(input_files)
 }
 
+/// Format a duration as a single machine-readable token, e.g. `1234us`
+fn duration_token(duration: time::Duration) -> String {
+    format!("{}us", duration.as_micros())
+}
+
+/// Where the input files of a command are read from
+enum Inputs {
+    /// Files of the input directory, read when solving
+    Directory(Vec<PathBuf>),
+
+    /// Entries of a tar archive, already read in memory
+    Archive(HashMap<PathBuf, String>),
+}
+
+impl Inputs {
+    /// The input files, leaving out the expectations of additional test files
+    fn files(&self) -> Vec<PathBuf> {
+        let files = match self {
+            Inputs::Directory(files) => files.clone(),
+            Inputs::Archive(entries) => entries.keys().cloned().collect(),
+        };
+
+        files
+            .into_iter()
+            .filter(|f| f.extension().is_none_or(|e| e != EXPECTED_EXTENSION))
+            .collect()
+    }
+
+    /// The expected answer of the test file `file`, if it has one
+    fn expected(&self, file: &Path) -> Option<String> {
+        let path = file.with_extension(EXPECTED_EXTENSION);
+
+        let expected = match self {
+            Inputs::Directory(_) => fs::read_to_string(path).ok(),
+            Inputs::Archive(entries) => entries.get(&path).cloned(),
+        };
+
+        expected.map(|e| e.trim_end().to_string())
+    }
+
+    fn solve(&self, file: &Path, day: usize, part: usize, args: &CommonArgs) -> day::SolverResult {
+        let options = args.options.clone();
+
+        match self {
+            Inputs::Directory(_) => {
+                let file = file.to_path_buf();
+                day::with_timeout(args.timeout, move || day::solve(file, day, part, &options))
+            }
+            Inputs::Archive(entries) => {
+                let text = entries[file].clone();
+                day::with_timeout(args.timeout, move || {
+                    day::solve_text(&text, day, part, &options)
+                })
+            }
+        }
+    }
+
+    fn test(&self, file: &Path, day: usize, part: usize, args: &CommonArgs) -> day::SolverResult {
+        let options = args.options.clone();
+        let expected = if args.all_files {
+            self.expected(file)
+        } else {
+            None
+        };
+
+        match self {
+            Inputs::Directory(_) => {
+                let file = file.to_path_buf();
+                day::with_timeout(args.timeout, move || {
+                    day::test(file, day, part, expected.as_deref(), &options)
+                })
+            }
+            Inputs::Archive(entries) => {
+                let text = entries[file].clone();
+                day::with_timeout(args.timeout, move || {
+                    day::test_text(&text, day, part, expected.as_deref(), &options)
+                })
+            }
+        }
+    }
+}
+
 #[derive(Eq, PartialEq)]
 enum FileType {
     Input,
@@ -197,49 +506,257 @@ impl Command {
     }
 
     fn parse(args: Vec<String>) -> Result<Self> {
-        let command = args.get(0).ok_or(Error::MissingCommand)?;
-        let command = command.to_lowercase();
+        let command = args.first().ok_or(Error::MissingCommand)?;
+        let mut command = command.to_lowercase();
+
+        if let Some((_, canonical)) = ALIASES.iter().find(|(alias, _)| *alias == command) {
+            command = canonical.to_string();
+        }
 
-        let is_valid = matches!(command.as_str(), "test" | "solve");
+        let is_valid = matches!(
+            command.as_str(),
+            "test" | "solve" | "report" | "verify" | "list" | "doctor" | "debug" | "all"
+        );
         if !is_valid {
             return Err(Error::InvalidCommand(command));
         }
 
+        // `all` is a shorthand for testing every registered day
+        if command == "all" {
+            let args = ["test", "all"]
+                .into_iter()
+                .map(String::from)
+                .chain(args[1..].iter().cloned())
+                .collect();
+
+            return Self::parse(args);
+        }
+
+        if command == "list" {
+            return Self::parse_list(&args[1..]);
+        }
+
+        if command == "doctor" {
+            return Self::parse_doctor(&args[1..]);
+        }
+
+        if command == "debug" {
+            return match args[1..] {
+                [ref topic] if topic == "registry" => Ok(Command::Debug(DebugTopic::Registry)),
+                [ref topic, ..] => Err(Error::InvalidDebugTopic(topic.clone())),
+                [] => Err(Error::InvalidDebugTopic(String::new())),
+            };
+        }
+
         let path = args
             .get(1)
             .ok_or(Error::MissingPath(command.clone()))
             .and_then(|p| ArgPath::from_str(p.as_str()).map_err(Error::InvalidPath))?;
 
-        let args = CommonArgs { path };
+        let mut common = CommonArgs {
+            path,
+            strict: false,
+            time_only: false,
+            stdout: false,
+            profile_alloc: false,
+            from_clipboard: false,
+            archive: None,
+            format: OutputFormat::Text,
+            watch: false,
+            timeout: None,
+            log: None,
+            group_digits: false,
+            part: None,
+            auto_test: false,
+            all_files: false,
+            input: None,
+            options: day::Options::default(),
+        };
+
+        let mut flags = args[2..].iter();
+        while let Some(flag) = flags.next() {
+            match flag.as_str() {
+                "--strict" if command != "test" => return Err(Error::InvalidFlag(flag.clone())),
+                "--strict" => common.strict = true,
+                "--time-only" if command != "solve" => {
+                    return Err(Error::InvalidFlag(flag.clone()))
+                }
+                "--time-only" => common.time_only = true,
+                "--stdout" => common.stdout = true,
+                "--profile-alloc" if !alloc::ENABLED => {
+                    return Err(Error::FeatureDisabled("profile-alloc"))
+                }
+                "--profile-alloc" => common.profile_alloc = true,
+                "--from-clipboard" if command != "solve" => {
+                    return Err(Error::InvalidFlag(flag.clone()))
+                }
+                "--from-clipboard" if !clipboard::ENABLED => {
+                    return Err(Error::FeatureDisabled("clipboard"))
+                }
+                "--from-clipboard" => common.from_clipboard = true,
+                "--archive" if !archive::ENABLED => return Err(Error::FeatureDisabled("archive")),
+                "--archive" => {
+                    let path = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    common.archive = Some(PathBuf::from(path));
+                }
+                "--format" => {
+                    let format = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    common.format = format.parse()?;
+                }
+                "--watch" if command != "test" => return Err(Error::InvalidFlag(flag.clone())),
+                "--watch" if !watch::ENABLED => return Err(Error::FeatureDisabled("watch")),
+                "--watch" => common.watch = true,
+                "--timeout" => {
+                    let timeout = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    let millis = timeout
+                        .parse()
+                        .map_err(|_| Error::InvalidTimeout(timeout.clone()))?;
+                    common.timeout = Some(Duration::from_millis(millis));
+                }
+                "--log" if command != "solve" => return Err(Error::InvalidFlag(flag.clone())),
+                "--log" => {
+                    let path = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    common.log = Some(PathBuf::from(path));
+                }
+                "--group-digits" if command != "solve" => {
+                    return Err(Error::InvalidFlag(flag.clone()))
+                }
+                "--group-digits" => common.group_digits = true,
+                "--auto-test" if command != "solve" => {
+                    return Err(Error::InvalidFlag(flag.clone()))
+                }
+                "--auto-test" => common.auto_test = true,
+                "--progress" => common.options.progress = true,
+                "--traversal" => {
+                    let traversal = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    common.options.traversal = Some(traversal.clone());
+                }
+                "--scoring" => {
+                    let table = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    common.options.scoring = Some(table.clone());
+                }
+                "--all-files" if command != "test" => return Err(Error::InvalidFlag(flag.clone())),
+                "--all-files" => common.all_files = true,
+                "--input" if command == "report" || command == "verify" => {
+                    return Err(Error::InvalidFlag(flag.clone()))
+                }
+                "--input" => {
+                    let file = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    let file = PathBuf::from(file);
+                    if !file.is_file() {
+                        return Err(Error::InvalidPath(ParsePathError::InvalidPath(file)));
+                    }
+
+                    common.input = Some(file);
+                }
+                "--part" => {
+                    let part = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    common.part = match part.as_str() {
+                        "1" => Some(1),
+                        "2" => Some(2),
+                        _ => return Err(Error::InvalidPart(part.clone())),
+                    };
+                }
+                "--skip-header" => {
+                    let count = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    common.options.skip_header = count
+                        .parse()
+                        .map_err(|_| Error::InvalidSkipHeader(count.clone()))?;
+                }
+                "--window" => {
+                    let window = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    let size = window
+                        .parse()
+                        .ok()
+                        .filter(|&size| size > 0)
+                        .ok_or(Error::InvalidWindow(window.clone()))?;
+                    common.options.window = Some(size);
+                }
+                "--days" => {
+                    let days = flags.next().ok_or(Error::MissingFlagValue(flag.clone()))?;
+                    let days = days.parse().map_err(|_| Error::InvalidDays(days.clone()))?;
+                    common.options.days = Some(days);
+                }
+                "--verbose" => common.options.verbose = true,
+                "--breakdown" => common.options.breakdown = true,
+                "--show-grid" => common.options.show_grid = true,
+                "--explain" => common.options.explain = true,
+                "--diagonals" => common.options.diagonals = true,
+                "--check" => common.options.check = true,
+                "--show-basins" => common.options.show_basins = true,
+                _ => return Err(Error::InvalidFlag(flag.clone())),
+            }
+        }
+
         Ok(match command.as_str() {
-            "test" => Command::Test(args),
-            "solve" => Command::Solve(args),
+            "test" => Command::Test(common),
+            "solve" => Command::Solve(common),
+            "report" => Command::Report(common),
+            "verify" => Command::Verify(common),
             _ => unreachable!(),
         })
     }
 
-    fn args(&self) -> &CommonArgs {
+    fn parse_list(flags: &[String]) -> Result<Self> {
+        let mut list = ListArgs { json: false };
+
+        for flag in flags {
+            match flag.as_str() {
+                "--json" => list.json = true,
+                _ => return Err(Error::InvalidFlag(flag.clone())),
+            }
+        }
+
+        Ok(Command::List(list))
+    }
+
+    fn parse_doctor(flags: &[String]) -> Result<Self> {
+        let mut doctor = DoctorArgs {
+            tests: false,
+            strict: false,
+        };
+
+        for flag in flags {
+            match flag.as_str() {
+                "--tests" => doctor.tests = true,
+                "--strict" => doctor.strict = true,
+                _ => return Err(Error::InvalidFlag(flag.clone())),
+            }
+        }
+
+        Ok(Command::Doctor(doctor))
+    }
+
+    /// The arguments of a command taking a path, `None` for the others
+    fn args(&self) -> Option<&CommonArgs> {
         match self {
-            Self::Solve(args) | Self::Test(args) => args,
+            Self::Solve(args) | Self::Test(args) | Self::Report(args) | Self::Verify(args) => {
+                Some(args)
+            }
+            Self::List(_) | Self::Doctor(_) | Self::Debug(_) => None,
         }
     }
 
-    fn resolve_input_files<P: AsRef<Path>>(
-        &self,
-        prefix_path: P,
-    ) -> Result<Vec<(ArgPath, PathBuf)>> {
-        let args = self.args();
+    fn resolve_input_files(&self, files: &[PathBuf]) -> Result<Vec<(ArgPath, PathBuf)>> {
+        // Commands without a path do not read any input
+        let args = match self.args() {
+            Some(args) => args,
+            None => return Ok(Vec::new()),
+        };
 
         let arg_fragment = args.path.fragment_index("part");
         let is_test = matches!(self, Self::Test(_));
 
         let mut input_files = Vec::new();
 
-        let files = read_input_files(prefix_path)?;
-        for file in &files {
-            let file_path = ArgPath::parse_path(&file).map_err(Error::InvalidPath)?;
+        for file in files {
+            let file_path = ArgPath::parse_path(file).map_err(Error::InvalidPath)?;
             if let Some(file_type) = get_file_type(&file_path) {
-                if let Some(fragment) = file_path.disjoint(&args.path) {
+                if args.path.is_all() {
+                    if (file_type == FileType::Test) == is_test {
+                        input_files.push((file_path, file.to_path_buf()));
+                    }
+                } else if let Some(fragment) = file_path.disjoint(&args.path) {
                     if fragment.prefix == "part" {
                         if file_type == FileType::Test && !is_test
                             || file_type == FileType::Input && is_test
@@ -254,9 +771,9 @@ impl Command {
                             (None, _) => input_files.push((file_path, file.to_path_buf())),
                             _ => {}
                         };
-                    } else if fragment.prefix == "input" && !is_test {
-                        input_files.push((file_path, file.to_path_buf()));
-                    } else if fragment.prefix == "test" && is_test {
+                    } else if (fragment.prefix == "input" && !is_test)
+                        || (fragment.prefix == "test" && is_test)
+                    {
                         input_files.push((file_path, file.to_path_buf()));
                     }
                 } else {
@@ -267,66 +784,1011 @@ impl Command {
             }
         }
 
+        if let Some(part) = args.part {
+            input_files.retain(|(path, _)| path.fragment_index("part") == Some(part));
+        }
+
+        // Additional test files, like `test2`, only run when asked for
+        if is_test && !args.all_files {
+            input_files.retain(|(path, _)| path.fragment_index("test").is_none());
+        }
+
+        // Test runs only ever pick test files, but a part solved from two different input files
+        // would give two answers for the same puzzle
+        if !is_test {
+            let mut candidates: HashMap<_, Vec<PathBuf>> = HashMap::new();
+            for (path, file) in &input_files {
+                let key = (path.fragment_index("day"), path.fragment_index("part"));
+                candidates.entry(key).or_default().push(file.clone());
+            }
+
+            let mut ambiguous = candidates
+                .into_values()
+                .filter(|files| files.len() > 1)
+                .flatten()
+                .collect::<Vec<_>>();
+
+            if !ambiguous.is_empty() {
+                ambiguous.sort();
+                return Err(Error::AmbiguousInput(ambiguous));
+            }
+        }
+
         Ok(input_files)
     }
 
+    fn run_list(args: &ListArgs) {
+        let days = day::catalog();
+
+        if args.json {
+            println!("{}", report::render_catalog_json(&days));
+            return;
+        }
+
+        for day in &days {
+            let status = if day.implemented {
+                ""
+            } else {
+                " (not implemented)"
+            };
+            println!("Day {} - {}{}", day.day, day.name, status);
+        }
+    }
+
+    fn run_debug_registry() {
+        for entry in day::registry_entries() {
+            let (module, name) = match entry.solver {
+                Some(solver) => solver,
+                None => {
+                    println!("Day {} -> <missing>", entry.day);
+                    continue;
+                }
+            };
+
+            let mut notes = Vec::new();
+            if !entry.implemented {
+                notes.push("not implemented");
+            }
+            if entry.is_misplaced() {
+                notes.push("module mismatch");
+            }
+
+            let notes = if notes.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", notes.join(", "))
+            };
+            println!("Day {} -> {} ({}){}", entry.day, module, name, notes);
+        }
+    }
+
+    /// The problems found by the checks selected in `args`
+    fn doctor_problems(args: &DoctorArgs) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if args.tests {
+            for (day_index, part_index) in day::placeholder_tests() {
+                let name = day::name(day_index).unwrap_or("Unknown");
+                problems.push(format!(
+                    "Day {} ({}) - Part {} has no test expectation",
+                    day_index, name, part_index
+                ));
+            }
+        }
+
+        problems
+    }
+
+    fn run_doctor(args: &DoctorArgs) -> Result<()> {
+        if !args.tests {
+            println!("No check selected, pass --tests to check the test expectations");
+            return Ok(());
+        }
+
+        let problems = Self::doctor_problems(args);
+        for problem in &problems {
+            println!("{}", problem);
+        }
+
+        if problems.is_empty() {
+            println!("No problems found");
+        } else if args.strict {
+            return Err(Error::PlaceholderTests(problems.len()));
+        }
+
+        Ok(())
+    }
+
+    /// Rebuild and run the tests of the day again whenever its source file changes
+    fn run_watch(args: &CommonArgs) -> Result<()> {
+        let day_index = args
+            .path
+            .fragment_index("day")
+            .ok_or(Error::ResolvePath(PathBuf::from(&args.path.value)))?;
+
+        let source = PathBuf::from(format!("src/day/day{}.rs", day_index));
+        if !source.is_file() {
+            return Err(Error::ResolvePath(source));
+        }
+
+        let test_args = std::env::args()
+            .skip(1)
+            .filter(|a| a != "--watch")
+            .collect::<Vec<_>>();
+
+        println!("Watching {:?}, press Ctrl-C to stop", source);
+
+        watch::watch(&source, || {
+            let status = process::Command::new("cargo")
+                .args(["run", "--quiet", "--"])
+                .args(&test_args)
+                .status();
+
+            match status {
+                Ok(status) if status.success() => println!("--- [PASS] waiting for changes"),
+                Ok(_) => println!("--- [FAIL] waiting for changes"),
+                Err(e) => println!("--- Failed to run cargo: {}", e),
+            }
+        })
+        .map_err(Error::Watch)
+    }
+
+    /// Solve the day designated by the path with the text held by the clipboard as input
+    fn run_clipboard(args: &CommonArgs) -> Result<()> {
+        let day_index = args
+            .path
+            .fragment_index("day")
+            .ok_or(Error::ResolvePath(PathBuf::from(&args.path.value)))?;
+
+        let parts = match args.part.or(args.path.fragment_index("part")) {
+            Some(part) => vec![part],
+            None => vec![1, 2],
+        };
+
+        let text = clipboard::read_text().map_err(Error::Clipboard)?;
+        let name = day::name(day_index).unwrap_or("Unknown");
+
+        for part_index in parts {
+            let start = time::Instant::now();
+
+            let (text, options) = (text.clone(), args.options.clone());
+            let result = day::with_timeout(args.timeout, move || {
+                day::solve_text(&text, day_index, part_index, &options)
+            })
+            .map_err(|e| Error::Solver(PathBuf::from("clipboard"), e))?;
+
+            if args.time_only {
+                println!("{}", duration_token(start.elapsed()));
+                continue;
+            }
+
+            println!(
+                "Solved Day {} ({}) - Part {} [clipboard] -> {}{}   [{:?}]",
+                day_index,
+                name,
+                part_index,
+                display_answer(&result, args.group_digits),
+                input_label(args, day_index, part_index, &result),
+                start.elapsed()
+            );
+        }
+
+        Ok(())
+    }
+
     pub(super) fn run(&self, prefix_path: impl AsRef<Path>) -> Result<()> {
-        let input_files = self.resolve_input_files(prefix_path)?;
+        let args = match self {
+            Command::List(args) => {
+                Self::run_list(args);
+                return Ok(());
+            }
+            Command::Doctor(args) => return Self::run_doctor(args),
+            Command::Debug(DebugTopic::Registry) => {
+                Self::run_debug_registry();
+                return Ok(());
+            }
+            Command::Solve(args)
+            | Command::Test(args)
+            | Command::Report(args)
+            | Command::Verify(args) => args,
+        };
+
+        if let Command::Solve(args) = self {
+            if args.from_clipboard {
+                return Self::run_clipboard(args);
+            }
+        }
+
+        if let Command::Test(args) = self {
+            if args.watch {
+                return Self::run_watch(args);
+            }
+        }
+
+        // An explicit input file is only opened when solving, which reports it if it is missing
+        let inputs = match (&args.input, &args.archive) {
+            (Some(file), _) => Inputs::Directory(vec![file.clone()]),
+            (None, Some(path)) => Inputs::Archive(
+                archive::read_entries(path).map_err(|e| Error::ReadArchive(path.clone(), e))?,
+            ),
+            (None, None) => Inputs::Directory(read_input_files(prefix_path)?),
+        };
+
+        let input_files = match &args.input {
+            Some(file) => explicit_input_parts(args, file)?,
+            None => self
+                .resolve_input_files(&inputs.files())?
+                .into_iter()
+                .map(|(path, file)| {
+                    let day_index = path
+                        .fragment_index("day")
+                        .ok_or(Error::ResolvePath(file.clone()))?;
+                    let part_index = path
+                        .fragment_index("part")
+                        .ok_or(Error::ResolvePath(file.clone()))?;
+
+                    Ok((day_index, part_index, file))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        };
+        let mut report_rows = Vec::new();
+        let mut log_rows = Vec::new();
+        let mut failed_tests = 0;
+        let mut wrong_answers = 0;
 
         if input_files.is_empty() {
-            let args = self.args();
-            println!("Could not find any input files for {}", args.path.value);
-        } else {
-            for (path, input_file) in &input_files {
-                let day_index = path
-                    .fragment_index("day")
-                    .ok_or(Error::ResolvePath(input_file.to_path_buf()))?;
+            if let Inputs::Archive(_) = inputs {
+                return Err(Error::MissingArchiveEntry(args.path.value.clone()));
+            }
+
+            return Err(Error::NoInputFound(args.path.value.clone()));
+        }
 
-                let part_index = path
-                    .fragment_index("part")
-                    .ok_or(Error::ResolvePath(input_file.to_path_buf()))?;
+        for &(day_index, part_index, ref input_file) in &input_files {
+            let name = day::name(day_index).unwrap_or("Unknown");
 
-                let name = day::name(day_index).unwrap_or("Unknown");
+            let start = time::Instant::now();
 
-                let start = time::Instant::now();
+            match self {
+                Command::Solve(args) => {
+                    let allocs = alloc::snapshot();
 
-                match self {
-                    Command::Solve(_) => {
-                        let result = day::solve(input_file, day_index, part_index)
-                            .map_err(|e| Error::SolverError(input_file.to_path_buf(), e))?;
+                    let result = inputs
+                        .solve(input_file, day_index, part_index, args)
+                        .map_err(|e| Error::Solver(input_file.to_path_buf(), e))?;
 
-                        println!(
-                            "Solved Day {} ({}) - Part {} [{:?}] -> {}   [{:?}]",
-                            day_index,
+                    if args.log.is_some() {
+                        log_rows.push(ReportRow {
+                            day: day_index,
                             name,
-                            part_index,
-                            input_file,
-                            result,
-                            start.elapsed()
+                            part: part_index,
+                            answer: result.clone(),
+                            elapsed: start.elapsed(),
+                        });
+                    }
+
+                    if args.profile_alloc {
+                        let allocs = alloc::snapshot().since(&allocs);
+                        println!(
+                            "Day {} - Part {} allocated {} times ({} bytes)",
+                            day_index, part_index, allocs.allocations, allocs.bytes
                         );
                     }
-                    Command::Test(_) => {
-                        match day::test(input_file, day_index, part_index) {
-                            Ok(result) => {
-                                println!(
-                                    "Test - Day {} ({}) - Part {} [{:?}]   [OK]  ({})   [{:?}]",
-                                    day_index,
-                                    name,
-                                    part_index,
-                                    input_file,
-                                    result,
-                                    start.elapsed()
-                                );
-                            }
-                            Err(e) => {
-                                println!("Test - Day {} ({}) - Part {} [{:?}]   [FAILED]  ({:?})   [{:?}]", day_index, name, part_index, input_file, e, start.elapsed());
-                            }
+
+                    if args.time_only {
+                        println!("{}", duration_token(start.elapsed()));
+                        continue;
+                    }
+
+                    if args.format != OutputFormat::Text {
+                        report_rows.push(ReportRow {
+                            day: day_index,
+                            name,
+                            part: part_index,
+                            answer: result,
+                            elapsed: start.elapsed(),
+                        });
+                        continue;
+                    }
+
+                    println!(
+                        "Solved Day {} ({}) - Part {} [{:?}] -> {}{}   [{:?}]",
+                        day_index,
+                        name,
+                        part_index,
+                        input_file,
+                        display_answer(&result, args.group_digits),
+                        input_label(args, day_index, part_index, &result),
+                        start.elapsed()
+                    );
+                }
+                Command::Test(args) => {
+                    match test_strict(&inputs, input_file, day_index, part_index, args)? {
+                        Ok(result) => {
+                            println!(
+                                "Test - Day {} ({}) - Part {} [{:?}]   [OK]  ({})   [{:?}]",
+                                day_index,
+                                name,
+                                part_index,
+                                input_file,
+                                result,
+                                start.elapsed()
+                            );
+
+                            report_rows.push(ReportRow {
+                                day: day_index,
+                                name,
+                                part: part_index,
+                                answer: result,
+                                elapsed: start.elapsed(),
+                            });
+                        }
+                        Err(e) => {
+                            failed_tests += 1;
+                            println!(
+                                "Test - Day {} ({}) - Part {} [{:?}]   [FAILED]  ({})   [{:?}]",
+                                day_index,
+                                name,
+                                part_index,
+                                input_file,
+                                e,
+                                start.elapsed()
+                            );
                         }
                     }
-                };
+                }
+                // These commands were run on their own above, they do not read any input
+                Command::List(_) | Command::Doctor(_) | Command::Debug(_) => {}
+                Command::Verify(args) => {
+                    let answer = inputs
+                        .solve(input_file, day_index, part_index, args)
+                        .map_err(|e| Error::Solver(input_file.to_path_buf(), e))?;
+
+                    let status = match day::verify_answer(day_index, part_index, &answer) {
+                        Some(true) => "OK",
+                        Some(false) => {
+                            wrong_answers += 1;
+                            "WRONG"
+                        }
+                        None => "UNKNOWN",
+                    };
+
+                    println!(
+                        "Verify - Day {} ({}) - Part {} [{:?}]   [{}]  ({})   [{:?}]",
+                        day_index,
+                        name,
+                        part_index,
+                        input_file,
+                        status,
+                        answer,
+                        start.elapsed()
+                    );
+                }
+                Command::Report(args) => {
+                    let answer = inputs
+                        .solve(input_file, day_index, part_index, args)
+                        .map_err(|e| Error::Solver(input_file.to_path_buf(), e))?;
+
+                    report_rows.push(ReportRow {
+                        day: day_index,
+                        name,
+                        part: part_index,
+                        answer,
+                        elapsed: start.elapsed(),
+                    });
+                }
+            };
+        }
+
+        if let Command::Solve(args) = self {
+            report_rows.sort_by_key(|r| (r.day, r.part));
+            match args.format {
+                OutputFormat::Text => {}
+                OutputFormat::Env => print!("{}", report::render_env(&report_rows)),
+                OutputFormat::Json => print!("{}", report::render_json(&report_rows)),
+            }
+
+            if let Some(path) = &args.log {
+                log_rows.sort_by_key(|r| (r.day, r.part));
+                append_log(path, &log_rows)?;
+            }
+        }
+
+        if let Command::Test(args) = self {
+            if args.all_files || args.path.is_all() {
+                report_rows.sort_by_key(|r| (r.day, r.part));
+                print!("{}", report::render_summary(&report_rows));
+                println!(
+                    "{}/{} test files passed",
+                    input_files.len() - failed_tests,
+                    input_files.len()
+                );
+            }
+
+            if failed_tests > 0 && (args.strict || args.all_files || args.path.is_all()) {
+                return Err(Error::FailedTests(failed_tests));
+            }
+        }
+
+        if wrong_answers > 0 {
+            return Err(Error::WrongAnswers(wrong_answers));
+        }
+
+        if let Command::Report(args) = self {
+            report_rows.sort_by_key(|r| (r.day, r.part));
+            let report = report::render_markdown(&report_rows);
+
+            if args.stdout {
+                print!("{}", report);
+            } else {
+                fs::write(REPORT_PATH, report)
+                    .map_err(|e| Error::WriteReport(PathBuf::from(REPORT_PATH), e))?;
+                println!("Report written to {}", REPORT_PATH);
             }
         }
 
         Ok(())
     }
 }
+
+/// The `(day, part, file)` runs of an explicit input `file`, for the day of the command path and
+/// the selected part, or both parts if none is
+fn explicit_input_parts(args: &CommonArgs, file: &Path) -> Result<Vec<(usize, usize, PathBuf)>> {
+    let day_index = args
+        .path
+        .fragment_index("day")
+        .ok_or(Error::ResolvePath(PathBuf::from(&args.path.value)))?;
+
+    let parts = match args.part.or(args.path.fragment_index("part")) {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
+
+    Ok(parts
+        .into_iter()
+        .map(|part| (day_index, part, file.to_path_buf()))
+        .collect())
+}
+
+/// Test `file`, also failing the test in strict mode if the solver wrote anything to stderr
+fn test_strict(
+    inputs: &Inputs,
+    file: &Path,
+    day: usize,
+    part: usize,
+    args: &CommonArgs,
+) -> Result<day::SolverResult> {
+    if !args.strict {
+        return Ok(inputs.test(file, day, part, args));
+    }
+
+    let (result, stderr) =
+        capture::stderr(|| inputs.test(file, day, part, args)).map_err(Error::CaptureStderr)?;
+
+    Ok(result.and_then(|answer| {
+        if stderr.is_empty() {
+            Ok(answer)
+        } else {
+            Err(day::SolverError::UnexpectedStderr(stderr))
+        }
+    }))
+}
+
+/// The answer as printed on the terminal, numeric answers being grouped by thousands if
+/// `group_digits` is set
+fn display_answer(result: &str, group_digits: bool) -> String {
+    match day::Answer::from(result) {
+        answer @ (day::Answer::UInt(_) | day::Answer::Int(_)) if group_digits => {
+            format!("{:#}", answer)
+        }
+        _ => result.to_string(),
+    }
+}
+
+/// With `--auto-test`, tell whether `result` comes from the sample input, going by the test
+/// expectation of the day, or from the real one
+fn input_label(args: &CommonArgs, day: usize, part: usize, result: &str) -> &'static str {
+    if !args.auto_test {
+        ""
+    } else if day::is_test_answer(day, part, result) {
+        " (test input)"
+    } else {
+        " (real input)"
+    }
+}
+
+/// Append a timestamped line per day of `rows` to the log file at `path`, creating it if needed
+fn append_log(path: &Path, rows: &[ReportRow]) -> Result<()> {
+    let secs = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let log = report::render_log(&report::format_timestamp(secs), rows);
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(log.as_bytes()))
+        .map_err(|e| Error::WriteLog(path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn should_parse_time_only() {
+        let command = Command::parse(args(&["solve", "day7", "--time-only"])).unwrap();
+        assert!(command.args().unwrap().time_only);
+
+        let result = Command::parse(args(&["test", "day7", "--time-only"]));
+        assert!(matches!(result, Err(Error::InvalidFlag(_))));
+    }
+
+    #[test]
+    fn should_parse_report_all() {
+        let command = Command::parse(args(&["report", "all", "--stdout"])).unwrap();
+        assert!(matches!(command, Command::Report(_)));
+        assert!(command.args().unwrap().path.is_all());
+        assert!(command.args().unwrap().stdout);
+    }
+
+    #[test]
+    fn should_print_only_duration_token() {
+        let token = duration_token(time::Duration::from_micros(1234));
+        assert_eq!(token, "1234us");
+
+        let digits = token.strip_suffix("us").unwrap();
+        assert!(digits.chars().all(|c| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn should_parse_list_json() {
+        let command = Command::parse(args(&["list", "--json"])).unwrap();
+        assert!(matches!(command, Command::List(ListArgs { json: true })));
+        assert!(command.args().is_none());
+
+        assert!(Command::parse(args(&["list", "day1"])).is_err());
+    }
+
+    #[test]
+    fn should_split_backslash_separated_fragments() {
+        let path = ArgPath::parse("day7\\part2").unwrap();
+        assert_eq!(path.fragment_index("day"), Some(7));
+        assert_eq!(path.fragment_index("part"), Some(2));
+
+        let mixed = ArgPath::parse("day7\\part2/test").unwrap();
+        assert_eq!(mixed.fragments.len(), 3);
+    }
+
+    #[test]
+    fn should_parse_windows_file_path() {
+        let path = ArgPath::parse_path("inputs\\day7.part2.test.txt").unwrap();
+        assert_eq!(path.value, "day7.part2.test.txt");
+        assert_eq!(path.fragment_index("day"), Some(7));
+        assert_eq!(path.fragment_index("part"), Some(2));
+        assert!(get_file_type(&path) == Some(FileType::Test));
+    }
+
+    #[test]
+    fn should_only_read_clipboard_when_solving() {
+        let result = Command::parse(args(&["test", "day7", "--from-clipboard"]));
+        assert!(matches!(result, Err(Error::InvalidFlag(_))));
+
+        let result = Command::parse(args(&["solve", "day7", "--from-clipboard"]));
+        if clipboard::ENABLED {
+            assert!(result.unwrap().args().unwrap().from_clipboard);
+        } else {
+            assert!(matches!(result, Err(Error::FeatureDisabled("clipboard"))));
+        }
+    }
+
+    #[test]
+    fn should_only_be_strict_when_testing() {
+        let result = Command::parse(args(&["solve", "day1", "--strict"]));
+        assert!(matches!(result, Err(Error::InvalidFlag(_))));
+
+        let command = Command::parse(args(&["test", "day1", "--strict"])).unwrap();
+        assert!(command.args().unwrap().strict);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_pass_strict_test_of_clean_day() {
+        let command = Command::parse(args(&["test", "day1", "--strict"])).unwrap();
+        let inputs = Inputs::Archive(HashMap::from([(
+            PathBuf::from("day1.part1.test.txt"),
+            "199\n200\n208\n210\n200\n207\n240\n269\n260\n263".to_string(),
+        )]));
+
+        let result = test_strict(
+            &inputs,
+            Path::new("day1.part1.test.txt"),
+            1,
+            1,
+            command.args().unwrap(),
+        );
+        assert_eq!(result.unwrap().unwrap(), "7");
+    }
+
+    #[test]
+    fn should_parse_doctor_tests_strict() {
+        let command = Command::parse(args(&["doctor", "--tests", "--strict"])).unwrap();
+        assert!(matches!(
+            command,
+            Command::Doctor(DoctorArgs {
+                tests: true,
+                strict: true
+            })
+        ));
+    }
+
+    #[test]
+    fn should_only_check_tests_when_asked() {
+        let doctor = match Command::parse(args(&["doctor", "--strict"])).unwrap() {
+            Command::Doctor(doctor) => doctor,
+            command => panic!("Expected a doctor command, got {:?}", command),
+        };
+        assert!(!doctor.tests);
+        assert!(Command::doctor_problems(&doctor).is_empty());
+        assert!(Command::run_doctor(&doctor).is_ok());
+
+        let doctor = match Command::parse(args(&["doctor", "--tests"])).unwrap() {
+            Command::Doctor(doctor) => doctor,
+            command => panic!("Expected a doctor command, got {:?}", command),
+        };
+        assert!(doctor.tests);
+        assert_eq!(
+            Command::doctor_problems(&doctor).len(),
+            day::placeholder_tests().len()
+        );
+        assert!(Command::run_doctor(&doctor).is_ok());
+    }
+
+    #[test]
+    fn should_parse_verify() {
+        let command = Command::parse(args(&["verify", "day1"])).unwrap();
+        assert!(matches!(command, Command::Verify(_)));
+
+        let result = Command::parse(args(&["verify", "day1", "--input", "Cargo.toml"]));
+        assert!(matches!(result, Err(Error::InvalidFlag(_))));
+    }
+
+    #[test]
+    fn should_parse_debug_registry() {
+        let command = Command::parse(args(&["debug", "registry"])).unwrap();
+        assert!(matches!(command, Command::Debug(DebugTopic::Registry)));
+
+        let result = Command::parse(args(&["debug", "inputs"]));
+        assert!(matches!(result, Err(Error::InvalidDebugTopic(t)) if t == "inputs"));
+        assert!(Command::parse(args(&["debug"])).is_err());
+    }
+
+    #[test]
+    fn should_require_archive_path() {
+        let result = Command::parse(args(&["solve", "day7", "--archive"]));
+
+        if archive::ENABLED {
+            assert!(matches!(result, Err(Error::MissingFlagValue(_))));
+
+            let command = Command::parse(args(&["solve", "day7", "--archive", "inputs.tar"]));
+            assert_eq!(
+                command.unwrap().args().unwrap().archive,
+                Some(PathBuf::from("inputs.tar"))
+            );
+        } else {
+            assert!(matches!(result, Err(Error::FeatureDisabled("archive"))));
+        }
+    }
+
+    #[test]
+    fn should_parse_env_format() {
+        let command = Command::parse(args(&["solve", "day7", "--format", "env"])).unwrap();
+        assert_eq!(command.args().unwrap().format, OutputFormat::Env);
+
+        let command = Command::parse(args(&["solve", "day7", "--format", "json"])).unwrap();
+        assert_eq!(command.args().unwrap().format, OutputFormat::Json);
+
+        let result = Command::parse(args(&["solve", "day7", "--format", "yaml"]));
+        assert!(matches!(result, Err(Error::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn should_only_watch_tests() {
+        let result = Command::parse(args(&["solve", "day12", "--watch"]));
+        assert!(matches!(result, Err(Error::InvalidFlag(_))));
+
+        let result = Command::parse(args(&["test", "day12", "--watch"]));
+        if watch::ENABLED {
+            assert!(result.unwrap().args().unwrap().watch);
+        } else {
+            assert!(matches!(result, Err(Error::FeatureDisabled("watch"))));
+        }
+    }
+
+    #[test]
+    fn should_parse_aliases_like_commands() {
+        let alias = Command::parse(args(&["s", "day7"])).unwrap();
+        let command = Command::parse(args(&["solve", "day7"])).unwrap();
+        assert_eq!(format!("{:?}", alias), format!("{:?}", command));
+
+        let alias = Command::parse(args(&["T", "day7/part1"])).unwrap();
+        assert!(matches!(alias, Command::Test(_)));
+
+        let result = Command::parse(args(&["x", "day7"]));
+        assert!(matches!(result, Err(Error::InvalidCommand(c)) if c == "x"));
+    }
+
+    #[test]
+    fn should_parse_timeout() {
+        let command = Command::parse(args(&["test", "day11", "--timeout", "500"])).unwrap();
+        assert_eq!(
+            command.args().unwrap().timeout,
+            Some(Duration::from_millis(500))
+        );
+
+        let result = Command::parse(args(&["test", "day11", "--timeout", "soon"]));
+        assert!(matches!(result, Err(Error::InvalidTimeout(_))));
+    }
+
+    #[test]
+    fn should_parse_skip_header() {
+        let command = Command::parse(args(&["solve", "day6", "--skip-header", "2"])).unwrap();
+        assert_eq!(command.args().unwrap().options.skip_header, 2);
+
+        let result = Command::parse(args(&["solve", "day6", "--skip-header", "-1"]));
+        assert!(matches!(result, Err(Error::InvalidSkipHeader(_))));
+    }
+
+    #[test]
+    fn should_parse_window() {
+        let command = Command::parse(args(&["solve", "day1", "--window", "5"])).unwrap();
+        assert_eq!(command.args().unwrap().options.window, Some(5));
+
+        for window in ["0", "wide"] {
+            let result = Command::parse(args(&["solve", "day1", "--window", window]));
+            assert!(matches!(result, Err(Error::InvalidWindow(w)) if w == window));
+        }
+    }
+
+    #[test]
+    fn should_parse_days() {
+        let command = Command::parse(args(&["solve", "day6", "--days", "18"])).unwrap();
+        assert_eq!(command.args().unwrap().options.days, Some(18));
+
+        let result = Command::parse(args(&["solve", "day6", "--days", "many"]));
+        assert!(matches!(result, Err(Error::InvalidDays(_))));
+    }
+
+    #[test]
+    fn should_append_log_lines() {
+        let path = std::env::temp_dir().join(format!("aoc2k21-{}.log", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let row = |part, answer| report::test_row(7, "The Treachery of Whales", part, answer);
+
+        append_log(&path, &[row(1, "37"), row(2, "168")]).unwrap();
+        append_log(&path, &[row(1, "37")]).unwrap();
+
+        let log = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines = log.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+
+        let fields = lines[0].split(' ').collect::<Vec<_>>();
+        assert_eq!(fields[1..], ["day7", "part1=37", "part2=168"]);
+        assert_eq!(fields[0].len(), "2021-12-07T10:00".len());
+        assert!(lines[1].ends_with(" day7 part1=37"));
+    }
+
+    #[test]
+    fn should_only_group_numeric_answers() {
+        assert_eq!(display_answer("26984457539", true), "26,984,457,539");
+        assert_eq!(display_answer("26984457539", false), "26984457539");
+        assert_eq!(display_answer("#.\n.#\n", true), "#.\n.#\n");
+    }
+
+    #[test]
+    fn should_parse_part() {
+        let command = Command::parse(args(&["solve", "day6", "--part", "2"])).unwrap();
+        assert_eq!(command.args().unwrap().part, Some(2));
+
+        let command = Command::parse(args(&["solve", "day6"])).unwrap();
+        assert_eq!(command.args().unwrap().part, None);
+
+        let result = Command::parse(args(&["solve", "day6", "--part", "3"]));
+        assert!(matches!(result, Err(Error::InvalidPart(_))));
+    }
+
+    #[test]
+    fn should_only_resolve_selected_part() {
+        let files = [
+            PathBuf::from("inputs/day6.part1.input.txt"),
+            PathBuf::from("inputs/day6.part2.input.txt"),
+            PathBuf::from("inputs/day7.part2.input.txt"),
+        ];
+
+        let command = Command::parse(args(&["solve", "day6", "--part", "2"])).unwrap();
+        let resolved = command.resolve_input_files(&files).unwrap();
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].1, files[1]);
+    }
+
+    #[test]
+    fn should_only_resolve_additional_test_files_when_asked() {
+        let files = [
+            PathBuf::from("inputs/day16.part1.test.txt"),
+            PathBuf::from("inputs/day16.part1.test2.txt"),
+            PathBuf::from("inputs/day16.part1.input.txt"),
+        ];
+
+        let command = Command::parse(args(&["test", "day16"])).unwrap();
+        let resolved = command.resolve_input_files(&files).unwrap();
+        assert_eq!(resolved.len(), 1);
+
+        let command = Command::parse(args(&["test", "day16", "--all-files"])).unwrap();
+        let resolved = command.resolve_input_files(&files).unwrap();
+        assert_eq!(resolved.len(), 2);
+
+        let result = Command::parse(args(&["solve", "day16", "--all-files"]));
+        assert!(matches!(result, Err(Error::InvalidFlag(_))));
+    }
+
+    #[test]
+    fn should_read_expectation_of_additional_test_files() {
+        let inputs = Inputs::Archive(HashMap::from([
+            (
+                PathBuf::from("day16.part1.test2.txt"),
+                "8A004A801A8002F478".to_string(),
+            ),
+            (
+                PathBuf::from("day16.part1.test2.expected"),
+                "16\n".to_string(),
+            ),
+        ]));
+
+        assert_eq!(inputs.files(), vec![PathBuf::from("day16.part1.test2.txt")]);
+        assert_eq!(
+            inputs.expected(Path::new("day16.part1.test2.txt")),
+            Some("16".to_string())
+        );
+        assert_eq!(inputs.expected(Path::new("day16.part1.test.txt")), None);
+    }
+
+    #[test]
+    fn should_label_sample_answers_as_test() {
+        let command = Command::parse(args(&["solve", "day1", "--auto-test"])).unwrap();
+        let sample = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+        let result = day::solve_text(sample, 1, 1, &command.args().unwrap().options).unwrap();
+
+        assert_eq!(
+            input_label(command.args().unwrap(), 1, 1, &result),
+            " (test input)"
+        );
+        assert_eq!(
+            input_label(command.args().unwrap(), 1, 1, "1688"),
+            " (real input)"
+        );
+
+        let command = Command::parse(args(&["solve", "day1"])).unwrap();
+        assert_eq!(input_label(command.args().unwrap(), 1, 1, &result), "");
+    }
+
+    #[test]
+    fn should_parse_all_as_test_all() {
+        let command = Command::parse(args(&["all", "--timeout", "500"])).unwrap();
+
+        assert!(matches!(command, Command::Test(_)));
+        assert!(command.args().unwrap().path.is_all());
+        assert_eq!(
+            command.args().unwrap().timeout,
+            Some(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn should_parse_input_file() {
+        let command = Command::parse(args(&[
+            "solve",
+            "day1",
+            "--input",
+            "inputs/day1.part1.test.txt",
+        ]))
+        .unwrap();
+        assert_eq!(
+            command.args().unwrap().input,
+            Some(PathBuf::from("inputs/day1.part1.test.txt"))
+        );
+
+        let result = Command::parse(args(&[
+            "solve",
+            "day1",
+            "--input",
+            "inputs/day1.missing.txt",
+        ]));
+        assert!(matches!(
+            result,
+            Err(Error::InvalidPath(ParsePathError::InvalidPath(path)))
+                if path == Path::new("inputs/day1.missing.txt")
+        ));
+    }
+
+    #[test]
+    fn should_run_each_part_of_input_file() {
+        let command = Command::parse(args(&[
+            "test",
+            "day1",
+            "--input",
+            "inputs/day1.part1.test.txt",
+        ]))
+        .unwrap();
+        let args = command.args().unwrap();
+
+        let file = PathBuf::from("inputs/day1.part1.test.txt");
+        assert_eq!(
+            explicit_input_parts(args, &file).unwrap(),
+            vec![(1, 1, file.clone()), (1, 2, file.clone())]
+        );
+    }
+
+    #[test]
+    fn should_fail_without_input_files() {
+        let dir = std::env::temp_dir().join(format!("aoc2k21-empty-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let command = Command::parse(args(&["solve", "day1"])).unwrap();
+        let result = command.run(&dir);
+        fs::remove_dir(&dir).unwrap();
+
+        assert!(matches!(result, Err(Error::NoInputFound(path)) if path == "day1"));
+    }
+
+    #[test]
+    fn should_reject_ambiguous_input_files() {
+        let files = [
+            PathBuf::from("inputs/day6.part1.input.txt"),
+            PathBuf::from("inputs/day6.part1.input2.txt"),
+            PathBuf::from("inputs/day6.part1.test.txt"),
+            PathBuf::from("inputs/day6.part2.input.txt"),
+        ];
+
+        let command = Command::parse(args(&["solve", "day6"])).unwrap();
+        let result = command.resolve_input_files(&files);
+        assert!(matches!(
+            result,
+            Err(Error::AmbiguousInput(candidates)) if candidates == files[..2]
+        ));
+
+        let command = Command::parse(args(&["test", "day6"])).unwrap();
+        let resolved = command.resolve_input_files(&files).unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].1, files[2]);
+    }
+
+    #[test]
+    fn should_only_index_trailing_digits() {
+        let fragment = |s| ArgPathFragment::parse(s).unwrap();
+        let expected = |prefix: &str, index| ArgPathFragment {
+            prefix: prefix.to_string(),
+            index,
+        };
+
+        assert_eq!(fragment("day1"), expected("day", Some(1)));
+        assert_eq!(fragment("day10"), expected("day", Some(10)));
+        assert_eq!(fragment("day1test"), expected("day1test", None));
+        assert_eq!(fragment("test"), expected("test", None));
+        assert_eq!(fragment("2x2grid"), expected("2x2grid", None));
+
+        assert!(matches!(
+            ArgPathFragment::parse("day99999999999999999999"),
+            Err(ParsePathError::InvalidIndex(..))
+        ));
+    }
+}
@@ -1,24 +1,76 @@
-use super::{Solver, SolverError, SolverResult};
+use super::{Options, Solver, SolverError, SolverResult};
 
+use std::cmp::Ordering;
 use std::result::Result;
 
-struct Day1;
+struct Day1 {
+    breakdown: bool,
 
-fn solve(depths: impl Iterator<Item = u64>) -> SolverResult {
-    let mut increase_count = 0usize;
+    /// Size of the sliding window overriding the default of both parts
+    window: Option<usize>,
+}
+
+/// Default size of the sliding window of the first part, comparing single depths
+const PART1_WINDOW: usize = 1;
+
+/// Default size of the sliding window of the second part
+const PART2_WINDOW: usize = 3;
+
+/// Count the `(increases, decreases, equals)` between each depth and the previous one
+fn classify_deltas(depths: impl Iterator<Item = u64>) -> (usize, usize, usize) {
+    let mut deltas = (0usize, 0usize, 0usize);
     let mut previous: Option<u64> = None;
 
     for depth in depths {
         if let Some(previous) = previous {
-            if depth > previous {
-                increase_count += 1;
+            match depth.cmp(&previous) {
+                Ordering::Greater => deltas.0 += 1,
+                Ordering::Less => deltas.1 += 1,
+                Ordering::Equal => deltas.2 += 1,
             }
         }
 
         previous = Some(depth);
     }
 
-    Ok(increase_count.to_string())
+    deltas
+}
+
+/// The sums of every `window` consecutive depths, `window` being at least 1
+fn window_sums(depths: &[u64], window: usize) -> impl Iterator<Item = u64> + '_ {
+    depths.windows(window).map(|w| w.iter().sum())
+}
+
+/// Count the `(increases, decreases, equals)` between each sum of `window` consecutive depths and
+/// the previous one. Fails for an empty window, which has no sum to compare
+fn window_deltas(depths: &[u64], window: usize) -> Result<(usize, usize, usize), SolverError> {
+    if window == 0 {
+        return Err(SolverError::Generic(
+            "Sliding window cannot be empty".into(),
+        ));
+    }
+
+    Ok(classify_deltas(window_sums(depths, window)))
+}
+
+/// Count how many sums of `window` consecutive depths are larger than the previous one. A
+/// window larger than the input gives no sum, hence no increase
+fn count_increases(depths: &[u64], window: usize) -> Result<usize, SolverError> {
+    window_deltas(depths, window).map(|(increases, _, _)| increases)
+}
+
+fn solve(depths: &[u64], window: usize, breakdown: bool) -> SolverResult {
+    if !breakdown {
+        return Ok(count_increases(depths, window)?.to_string());
+    }
+
+    let (increases, decreases, equals) = window_deltas(depths, window)?;
+    println!(
+        "increases={} decreases={} equals={}",
+        increases, decreases, equals
+    );
+
+    Ok(increases.to_string())
 }
 
 fn parse_depths(lines: Vec<String>) -> Result<Vec<u64>, SolverError> {
@@ -35,13 +87,13 @@ impl Solver for Day1 {
     }
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        parse_depths(lines).and_then(|d| solve(d.into_iter()))
+        let window = self.window.unwrap_or(PART1_WINDOW);
+        parse_depths(lines).and_then(|d| solve(&d, window, self.breakdown))
     }
 
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        let depths = parse_depths(lines)?;
-        let window_sums = depths.as_slice().windows(3).map(|w| w.iter().sum());
-        solve(window_sums)
+        let window = self.window.unwrap_or(PART2_WINDOW);
+        parse_depths(lines).and_then(|d| solve(&d, window, self.breakdown))
     }
 
     fn test_expected(&self, part: usize) -> &'static str {
@@ -51,8 +103,52 @@ impl Solver for Day1 {
             _ => unreachable!(),
         }
     }
+
+    fn real_expected(&self, part: usize) -> Option<&'static str> {
+        match part {
+            1 => Some("1688"),
+            2 => Some("1728"),
+            _ => None,
+        }
+    }
+
+    fn configure(&mut self, options: &Options) {
+        self.breakdown = options.breakdown;
+        self.window = options.window;
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day1)
+    Box::new(Day1 {
+        breakdown: false,
+        window: None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_classify_every_kind_of_delta() {
+        let depths = [199, 200, 200, 208, 207, 207, 207, 240, 200];
+
+        assert_eq!(classify_deltas(depths.into_iter()), (3, 2, 3));
+        assert_eq!(classify_deltas([199].into_iter()), (0, 0, 0));
+    }
+
+    #[test]
+    fn should_count_increases_of_any_window() {
+        let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+        assert_eq!(count_increases(&depths, 1).unwrap(), 7);
+        assert_eq!(count_increases(&depths, 3).unwrap(), 5);
+        assert_eq!(count_increases(&depths, 10).unwrap(), 0);
+        assert_eq!(count_increases(&depths, 11).unwrap(), 0);
+    }
+
+    #[test]
+    fn should_reject_empty_window() {
+        assert!(count_increases(&[199, 200, 208], 0).is_err());
+    }
 }
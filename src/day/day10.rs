@@ -1,4 +1,4 @@
-use super::{Solver, SolverError, SolverResult};
+use super::{Options, Solver, SolverError, SolverResult};
 
 use std::convert::TryFrom;
 use std::fmt::{self, Write};
@@ -64,10 +64,10 @@ enum SyntaxError {
 impl fmt::Display for SyntaxError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            SyntaxError::InvalidToken(c) => write!(f, "Invalid token {:?}", c),
             SyntaxError::InvalidClosing { got, expected } => {
                 write!(f, "Expected {}, but found {} instead", expected, got)
             }
-            _ => write!(f, "{:?}", self),
         }
     }
 }
@@ -85,6 +85,16 @@ impl Token {
         }
     }
 
+    /// The index of the pair of brackets the token belongs to, in `()`, `[]`, `{}`, `<>` order
+    fn pair(&self) -> usize {
+        match self {
+            Token::OpeningParenthesis | Token::ClosingParenthesis => 0,
+            Token::OpeningSquareBracket | Token::ClosingSquareBracket => 1,
+            Token::OpeningBracket | Token::ClosingBracket => 2,
+            Token::OpeningAngleBracket | Token::ClosingAngleBracket => 3,
+        }
+    }
+
     fn kind(&self) -> TokenKind {
         match self {
             Token::OpeningParenthesis
@@ -146,7 +156,6 @@ impl FromStr for Line {
         let mut chunks = Vec::new();
 
         for token in &tokens {
-            let token = token;
             match token.kind() {
                 TokenKind::Opening => chunks.push(*token),
                 TokenKind::Closing => {
@@ -171,7 +180,120 @@ impl FromStr for Line {
     }
 }
 
-struct Day10;
+/// The points scored by each pair of brackets, indexed by `Token::pair`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ScoringTable {
+    /// The points scored by an illegal closing token
+    corruption: [u64; 4],
+
+    /// The points scored by a token completing a line
+    completion: [u64; 4],
+}
+
+impl Default for ScoringTable {
+    fn default() -> Self {
+        ScoringTable {
+            corruption: [3, 57, 1197, 25137],
+            completion: [1, 2, 3, 4],
+        }
+    }
+}
+
+/// Parse a table written as `corruption/completion`, each of them being the four points of the
+/// `)`, `]`, `}` and `>` tokens separated by commas, e.g. `3,57,1197,25137/1,2,3,4`
+impl FromStr for ScoringTable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn points(s: &str) -> Result<[u64; 4], String> {
+            let points = s
+                .split(',')
+                .map(|p| p.trim().parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Invalid points {:?}: {}", s, e))?;
+
+            points
+                .try_into()
+                .map_err(|_| format!("Expected four points, got {:?}", s))
+        }
+
+        let (corruption, completion) = s.split_once('/').ok_or(format!(
+            "Expected corruption/completion points, got {:?}",
+            s
+        ))?;
+
+        Ok(ScoringTable {
+            corruption: points(corruption)?,
+            completion: points(completion)?,
+        })
+    }
+}
+
+impl ScoringTable {
+    fn corruption_points(&self, token: Token) -> u64 {
+        self.corruption[token.pair()]
+    }
+
+    fn completion_points(&self, token: Token) -> u64 {
+        self.completion[token.pair()]
+    }
+}
+
+/// The index, first illegal closing token and points of every corrupted line
+fn corruption_report(lines: &[String], table: &ScoringTable) -> Vec<(usize, Token, u64)> {
+    let mut report = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        match line.parse::<Line>() {
+            Ok(_) => {}
+            Err(SyntaxError::InvalidClosing { got, .. }) => {
+                report.push((index, got, table.corruption_points(got)))
+            }
+            // A character that is not a token scores nothing, the line is discarded like any
+            // other corrupted line
+            Err(SyntaxError::InvalidToken(_)) => {}
+        }
+    }
+
+    report
+}
+
+/// The completion score of every incomplete line, complete lines having nothing to score
+fn completion_scores(lines: &[String], table: &ScoringTable) -> Vec<u64> {
+    let incomplete_lines = lines
+        .iter()
+        .filter_map(|l| Line::from_str(l).ok())
+        .filter(|l| !l.chunks.is_empty());
+
+    let mut scores = Vec::new();
+
+    for incomplete_line in incomplete_lines {
+        let complete_tokens = incomplete_line.chunks.iter().rev().map(|t| t.closing());
+
+        let score =
+            complete_tokens.fold(0u64, |acc, token| acc * 5 + table.completion_points(token));
+
+        scores.push(score);
+    }
+
+    scores
+}
+
+struct Day10 {
+    /// The scoring table given on the command line, the AoC one otherwise
+    scoring: Option<String>,
+}
+
+impl Day10 {
+    fn scoring_table(&self) -> Result<ScoringTable, SolverError> {
+        match &self.scoring {
+            Some(table) => table
+                .parse()
+                .map_err(|e: String| SolverError::Generic(e.into())),
+            None => Ok(ScoringTable::default()),
+        }
+    }
+}
 
 impl Solver for Day10 {
     fn name(&self) -> &'static str {
@@ -179,56 +301,16 @@ impl Solver for Day10 {
     }
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        let mut score = 0u64;
-
-        for line in lines {
-            let line = line.parse::<Line>();
-            if let Err(e) = line {
-                if let SyntaxError::InvalidClosing { got, .. } = e {
-                    score += match got {
-                        Token::ClosingParenthesis => 3,
-                        Token::ClosingSquareBracket => 57,
-                        Token::ClosingBracket => 1197,
-                        Token::ClosingAngleBracket => 25137,
-                        _ => unreachable!(),
-                    };
-                } else {
-                    return Err(SolverError::Generic(e.into()));
-                }
-            }
-        }
+        let score: u64 = corruption_report(&lines, &self.scoring_table()?)
+            .into_iter()
+            .map(|(_, _, points)| points)
+            .sum();
 
         Ok(score.to_string())
     }
 
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        let incomplete_lines = lines
-            .iter()
-            .map(|l| Line::from_str(&l))
-            .filter(|l| l.is_ok())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| SolverError::Generic(e.into()))?;
-
-        let mut scores = Vec::new();
-
-        for incomplete_line in incomplete_lines {
-            let complete_tokens = incomplete_line.chunks.iter().rev().map(|t| t.closing());
-
-            let score = complete_tokens.fold(0u64, |acc, token| {
-                let mut score = acc * 5;
-                score += match token {
-                    Token::ClosingParenthesis => 1,
-                    Token::ClosingSquareBracket => 2,
-                    Token::ClosingBracket => 3,
-                    Token::ClosingAngleBracket => 4,
-                    _ => unreachable!(),
-                };
-
-                score
-            });
-
-            scores.push(score);
-        }
+        let mut scores = completion_scores(&lines, &self.scoring_table()?);
 
         scores.sort();
         let median = scores.len() / 2;
@@ -246,8 +328,95 @@ impl Solver for Day10 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.scoring = options.scoring.clone();
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day10)
+    Box::new(Day10 { scoring: None })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = "[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]";
+
+    #[test]
+    fn should_report_sample_corrupted_lines() {
+        let lines = SAMPLE.lines().map(String::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            corruption_report(&lines, &ScoringTable::default()),
+            vec![
+                (2, Token::ClosingBracket, 1197),
+                (4, Token::ClosingParenthesis, 3),
+                (5, Token::ClosingSquareBracket, 57),
+                (7, Token::ClosingParenthesis, 3),
+                (8, Token::ClosingAngleBracket, 25137),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_discard_lines_with_invalid_tokens() {
+        let mut lines = SAMPLE.lines().map(String::from).collect::<Vec<_>>();
+        lines.insert(3, "[(<x>)]".to_string());
+        lines.push("{([(<{}[<>[]}>{?".to_string());
+
+        let report = corruption_report(&lines, &ScoringTable::default());
+        assert_eq!(report.len(), 5);
+
+        let day = Day10 { scoring: None };
+        assert_eq!(day.solve_part1(lines).unwrap(), "26397");
+    }
+
+    #[test]
+    fn should_score_with_alternate_table() {
+        let lines = SAMPLE.lines().map(String::from).collect::<Vec<_>>();
+        let table = "1,10,100,1000/4,3,2,1".parse::<ScoringTable>().unwrap();
+
+        let points = corruption_report(&lines, &table)
+            .into_iter()
+            .map(|(_, _, points)| points)
+            .collect::<Vec<_>>();
+        assert_eq!(points, vec![100, 1, 10, 1, 1000]);
+
+        // The first incomplete line is completed by }}]])})]
+        let scores = completion_scores(&lines, &table);
+        assert_eq!(scores[0], 199323);
+
+        assert!("1,2,3/1,2,3,4".parse::<ScoringTable>().is_err());
+        assert!("1,2,3,4".parse::<ScoringTable>().is_err());
+    }
+
+    #[test]
+    fn should_only_score_incomplete_lines() {
+        let lines = [
+            "{([(<{}[<>[]}>{[]{[(<()>",
+            "[({(<(())[]>[[{[]{<()<>>",
+            "[<>({}){}[([])<>]]",
+        ]
+        .map(String::from);
+
+        let scores = completion_scores(&lines, &ScoringTable::default());
+        assert_eq!(scores, vec![288957]);
+
+        // The last line is complete, it has nothing left to score
+        assert!(completion_scores(&lines[2..], &ScoringTable::default()).is_empty());
+
+        let day = Day10 { scoring: None };
+        assert_eq!(day.solve_part2(lines.to_vec()).unwrap(), "288957");
+    }
 }
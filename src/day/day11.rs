@@ -1,6 +1,12 @@
-use super::{Solver, SolverError, SolverResult};
+use super::grid;
+use super::{Options, Solver, SolverError, SolverResult};
 
-struct Day11;
+use std::fmt;
+use std::ops::Range;
+
+struct Day11 {
+    show_grid: bool,
+}
 
 #[derive(Debug, Copy, Clone)]
 enum OctopusState {
@@ -12,6 +18,14 @@ enum OctopusState {
 struct Octopus(OctopusState);
 
 impl Octopus {
+    /// The energy level of the octopus, flashed octopuses are back to 0
+    fn energy(&self) -> u32 {
+        match self.0 {
+            OctopusState::Ready(x) => x,
+            OctopusState::Flashed(_) => 0,
+        }
+    }
+
     fn increase(&mut self) -> OctopusState {
         self.0 = match self.0 {
             OctopusState::Ready(x) => {
@@ -39,6 +53,20 @@ impl Octopus {
     }
 }
 
+/// A rectangle of the grid in which flashes are tallied
+#[derive(Debug, Clone)]
+struct Region {
+    rows: Range<usize>,
+
+    columns: Range<usize>,
+}
+
+impl Region {
+    fn contains(&self, x: usize, y: usize) -> bool {
+        self.rows.contains(&x) && self.columns.contains(&y)
+    }
+}
+
 struct Grid {
     octopuses: Vec<Octopus>,
 
@@ -52,117 +80,86 @@ impl Grid {
         self.octopuses.get_mut(x * self.columns + y)
     }
 
-    fn reset(&mut self) -> usize {
+    /// Reset the octopuses which flashed, returning how many of them are within `region`
+    fn reset(&mut self, region: &Region) -> usize {
         let mut total_flashed = 0;
 
-        for octopus in &mut self.octopuses {
+        for (index, octopus) in self.octopuses.iter_mut().enumerate() {
             if octopus.flashed() {
                 octopus.reset();
-                total_flashed += 1;
+
+                if region.contains(index / self.columns, index % self.columns) {
+                    total_flashed += 1;
+                }
             }
         }
 
         total_flashed
     }
 
+    fn region(&self) -> Region {
+        Region {
+            rows: 0..self.rows,
+            columns: 0..self.columns,
+        }
+    }
+
     fn len(&self) -> usize {
         self.octopuses.len()
     }
 
     fn get_adjacent(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
-        const DIRECTIONS: &'static [(i32, i32)] = &[
-            (0, -1),
-            (0, 1),
-            (-1, 0),
-            (1, 0),
-            (1, -1),
-            (1, 1),
-            (-1, -1),
-            (-1, 1),
-        ];
-
-        let rows = self.rows - 1;
-        let columns = self.columns - 1;
-
-        DIRECTIONS.iter().filter_map(move |d| {
-            let (d_x, d_y) = d;
-
-            let (x, y) = {
-                let x = if *d_x < 0 {
-                    x.checked_sub(d_x.abs() as usize)
-                } else {
-                    Some(x + *d_x as usize)
-                };
-
-                let y = if *d_y < 0 {
-                    y.checked_sub(d_y.abs() as usize)
-                } else {
-                    Some(y + *d_y as usize)
-                };
-
-                (x, y)
-            };
-
-            match (x, y) {
-                (Some(x), Some(y)) => {
-                    if x > rows || y > columns {
-                        None
-                    } else {
-                        Some((x, y))
-                    }
-                }
-                _ => None,
-            }
-        })
+        grid::neighbors((x, y), (self.rows, self.columns), true)
     }
 }
 
-fn parse_line(line: &str) -> Option<Vec<Octopus>> {
-    line.chars()
-        .map(|c| c.to_digit(10).map(|d| Octopus(OctopusState::Ready(d))))
-        .collect::<Option<Vec<_>>>()
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Octopuses which just flashed are back to 0, highlight them
+        grid::render_ansi(f, (self.rows, self.columns), |x, y| {
+            let energy = self.octopuses[x * self.columns + y].energy();
+            (energy, energy == 0)
+        })
+    }
 }
 
 fn parse_grid(lines: Vec<String>) -> Result<Grid, SolverError> {
-    let mut octopuses = Vec::new();
-    let mut columns = 0usize;
-    for line in &lines {
-        octopuses.extend(parse_line(&line).ok_or(SolverError::Generic("Invalid line".into()))?);
-
-        if columns > 0 && line.len() != columns {
-            return Err(SolverError::Generic("Not a grid".into()));
-        }
-
-        columns = line.len();
-    }
+    let cells = grid::Grid::from_lines(&lines, |c| {
+        c.to_digit(10).map(|d| Octopus(OctopusState::Ready(d)))
+    })?;
 
     Ok(Grid {
-        octopuses,
-        rows: lines.len(),
-        columns,
+        rows: cells.rows(),
+        columns: cells.columns(),
+        octopuses: cells.into_cells(),
     })
 }
 
-fn increase(grid: &mut Grid, x: usize, y: usize) {
-    let octopus = grid.get_octopus_at_mut(x, y).unwrap();
-    let state = octopus.increase();
-
-    // This is the first time this little guy flashes, increase adjacent
-    if let OctopusState::Flashed(1) = state {
-        for (adj_x, adj_y) in grid.get_adjacent(x, y) {
-            increase(grid, adj_x, adj_y);
+fn run_step(grid: &mut Grid, region: &Region) -> usize {
+    // Every octopus gets increased once, then once more by each adjacent flash. The order does
+    // not matter, so a stack keeps the cascade from growing the call stack
+    let mut pending = Vec::with_capacity(grid.rows * grid.columns);
+    for i in 0..grid.rows {
+        for j in 0..grid.columns {
+            pending.push((i, j));
         }
     }
-}
 
-fn run_step(grid: &mut Grid) -> usize {
-    for i in 0..grid.rows {
-        for j in 0..grid.columns {
-            increase(grid, i, j);
+    while let Some((x, y)) = pending.pop() {
+        let octopus = grid.get_octopus_at_mut(x, y).unwrap();
+
+        // This is the first time this little guy flashes, increase adjacent
+        if let OctopusState::Flashed(1) = octopus.increase() {
+            pending.extend(grid.get_adjacent(x, y));
         }
     }
 
-    grid.reset()
+    grid.reset(region)
+}
+
+/// Count the flashes happening within `region` over `steps` steps
+fn count_flashes(grid: &mut Grid, steps: usize, region: &Region) -> usize {
+    (0..steps).map(|_| run_step(grid, region)).sum()
 }
 
 impl Solver for Day11 {
@@ -172,9 +169,11 @@ impl Solver for Day11 {
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
         let mut grid = parse_grid(lines)?;
-        let mut total_flashes = 0usize;
-        for _step in 0..100 {
-            total_flashes += run_step(&mut grid);
+        let region = grid.region();
+        let total_flashes = count_flashes(&mut grid, 100, &region);
+
+        if self.show_grid {
+            print!("{}", grid);
         }
 
         Ok(total_flashes.to_string())
@@ -182,11 +181,12 @@ impl Solver for Day11 {
 
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
         let mut grid = parse_grid(lines)?;
+        let region = grid.region();
         let step = {
             let mut step = 1usize;
 
             loop {
-                let flashes = run_step(&mut grid);
+                let flashes = run_step(&mut grid, &region);
                 // Did they all flash ?
                 if flashes == grid.len() {
                     break;
@@ -198,6 +198,10 @@ impl Solver for Day11 {
             step
         };
 
+        if self.show_grid {
+            print!("{}", grid);
+        }
+
         Ok(step.to_string())
     }
 
@@ -208,8 +212,81 @@ impl Solver for Day11 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.show_grid = options.show_grid;
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day11)
+    Box::new(Day11 { show_grid: false })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_render_flashed_octopuses() {
+        let lines = ["11111", "19991", "19191", "19991", "11111"];
+        let mut grid = parse_grid(lines.iter().map(|l| l.to_string()).collect()).unwrap();
+
+        let region = grid.region();
+        assert_eq!(run_step(&mut grid, &region), 9);
+
+        let rendered = grid.to_string();
+        let expected = "34543\n4{0}{0}{0}4\n5{0}{0}{0}5\n4{0}{0}{0}4\n34543\n"
+            .replace("{0}", "\x1b[1m0\x1b[0m");
+
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn should_count_flashes_within_region() {
+        let lines = ["11111", "19991", "19191", "19991", "11111"];
+        let parse = || parse_grid(lines.iter().map(|l| l.to_string()).collect()).unwrap();
+
+        let top_left = Region {
+            rows: 0..3,
+            columns: 0..3,
+        };
+
+        let mut grid = parse();
+        let whole = grid.region();
+        assert_eq!(count_flashes(&mut grid, 2, &whole), 9);
+        assert_eq!(count_flashes(&mut parse(), 2, &top_left), 4);
+    }
+
+    #[test]
+    fn should_cascade_flashes_across_large_grid() {
+        // A single flash in the corner sets off every other octopus, one neighbour after the
+        // other, which is deep enough to overflow the stack of a recursive cascade
+        let size = 300;
+        let mut lines = vec!["8".repeat(size); size];
+        lines[0].replace_range(0..1, "9");
+
+        let mut grid = parse_grid(lines).unwrap();
+        let region = grid.region();
+        assert_eq!(run_step(&mut grid, &region), size * size);
+    }
+
+    #[test]
+    fn should_solve_sample() {
+        let lines = [
+            "5483143223",
+            "2745854711",
+            "5264556173",
+            "6141336146",
+            "6357385478",
+            "4167524645",
+            "2176841721",
+            "6882881134",
+            "4846848554",
+            "5283751526",
+        ]
+        .map(String::from);
+
+        assert_eq!(new().solve_part1(lines.to_vec()).unwrap(), "1656");
+        assert_eq!(new().solve_part2(lines.to_vec()).unwrap(), "195");
+    }
 }
@@ -1,6 +1,6 @@
-use super::{Solver, SolverError, SolverResult};
+use super::{Options, Progress, Solver, SolverError, SolverResult};
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::str::FromStr;
 
@@ -19,6 +19,15 @@ enum Cave {
     Big(String),
 }
 
+impl fmt::Display for CaveTryFromError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaveTryFromError::Empty => write!(f, "empty cave name"),
+            CaveTryFromError::Invalid(name) => write!(f, "invalid cave name {:?}", name),
+        }
+    }
+}
+
 impl FromStr for Cave {
     type Err = CaveTryFromError;
 
@@ -129,11 +138,12 @@ trait VisitRule {
     /// Create a `Self::Path` from a `path`
     fn create_path(path: Vec<NodeIndex>) -> Self::Path;
 
-    /// Get the `path` from a `Self::Path`
-    fn get_path(path: Self::Path) -> Vec<NodeIndex>;
-
     /// Return the last element of `Self::Path`
     fn last(path: &Self::Path) -> NodeIndex;
+
+    /// Whether a small cave that is already on the path can be visited again, knowing whether a
+    /// small cave has already been visited twice
+    fn revisit(visited_twice: bool) -> bool;
 }
 
 struct VisitBigMultipleSmallOnce;
@@ -173,13 +183,13 @@ impl VisitRule for VisitBigMultipleSmallOnce {
         path
     }
 
-    fn get_path(path: Self::Path) -> Vec<NodeIndex> {
-        path
-    }
-
     fn last(path: &Self::Path) -> NodeIndex {
         path[path.len() - 1]
     }
+
+    fn revisit(_visited_twice: bool) -> bool {
+        false
+    }
 }
 
 struct VisitBigMultipleSingleSmallTwiceOtherOnce;
@@ -208,7 +218,7 @@ impl VisitRule for VisitBigMultipleSingleSmallTwiceOtherOnce {
         // How many times did we alredy visit this cave ?
         let n_visited = current_path.1.iter().filter(|&&n| n == node_index).count();
 
-        let res = match (n_visited, current_path.0, data) {
+        match (n_visited, current_path.0, data) {
             // We never visited that cave yet
             (0, _, _) => {
                 let mut new_path = current_path.clone();
@@ -228,22 +238,20 @@ impl VisitRule for VisitBigMultipleSingleSmallTwiceOtherOnce {
             }
 
             _ => None,
-        };
-
-        res
+        }
     }
 
     fn create_path(path: Vec<NodeIndex>) -> Self::Path {
         (false, path)
     }
 
-    fn get_path(path: Self::Path) -> Vec<NodeIndex> {
-        path.1
-    }
-
     fn last(path: &Self::Path) -> NodeIndex {
         path.1[path.1.len() - 1]
     }
+
+    fn revisit(visited_twice: bool) -> bool {
+        !visited_twice
+    }
 }
 
 #[derive(Debug)]
@@ -265,7 +273,10 @@ impl std::error::Error for CaveError {}
 
 impl fmt::Display for CaveError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            CaveError::InvalidCave(e) => write!(f, "invalid cave: {}", e),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
@@ -318,22 +329,26 @@ impl CaveSystem {
         Ok((source, target))
     }
 
-    fn find_paths<V: VisitRule>(&self, start: NodeIndex, target: NodeIndex) -> Vec<Vec<NodeIndex>> {
+    /// Walk every path from `start` to `target`, calling `on_path` for each of them
+    fn traverse<V: VisitRule, F: FnMut(V::Path)>(
+        &self,
+        start: NodeIndex,
+        target: NodeIndex,
+        mut on_path: F,
+    ) {
         // The queue of possible paths
         let mut path_queue: VecDeque<V::Path> = VecDeque::new();
 
         // Enqueue the starting point
         path_queue.push_back(V::create_path(vec![start]));
 
-        let mut paths = Vec::new();
-
         while let Some(current_path) = path_queue.pop_front() {
             // The last element of our current path
             let last = V::last(&current_path);
 
-            // If we reached our destination, add the current path the list of paths and continue
+            // If we reached our destination, hand the current path over and continue
             if last == target {
-                paths.push(V::get_path(current_path));
+                on_path(current_path);
                 continue;
             }
 
@@ -346,7 +361,7 @@ impl CaveSystem {
                 let target = edge.target;
                 let target_node = &self.graph.nodes[target.0];
 
-                if let Some(new_path) = V::visit(&self.graph, &current_path, target, &target_node) {
+                if let Some(new_path) = V::visit(&self.graph, &current_path, target, target_node) {
                     path_queue.push_back(new_path);
                 }
 
@@ -354,12 +369,184 @@ impl CaveSystem {
                 edge_index = edge.next;
             }
         }
+    }
+
+    /// Count the paths with the breadth-first traversal
+    fn count_paths_bfs<V: VisitRule>(&self, progress: Progress) -> usize {
+        let mut counter = PathCounter::new(progress);
+        self.traverse::<V, _>(self.entry, self.exit, |_| counter.found_path());
+
+        counter.found
+    }
+
+    fn count_paths_with<V: VisitRule>(&self, traversal: Traversal, progress: Progress) -> usize {
+        match traversal {
+            Traversal::Bfs => self.count_paths_bfs::<V>(progress),
+            Traversal::Dfs => self.count_paths::<V>(progress),
+        }
+    }
+
+    /// Count the paths from the entry to the exit without storing them
+    fn count_paths<V: VisitRule>(&self, progress: Progress) -> usize {
+        let mut counter = PathCounter::new(progress);
+        self.walk_paths::<V, _>(|_| counter.found_path());
+
+        counter.found
+    }
+
+    /// Walk every path from the entry to the exit without storing them, calling `on_path` with
+    /// the number of caves each of them visits
+    fn walk_paths<V: VisitRule, F: FnMut(usize)>(&self, mut on_path: F) {
+        // The small caves on the current path, indexed by `NodeIndex`
+        let mut visited = vec![false; self.graph.nodes.len()];
+        visited[self.entry.0] = true;
+
+        self.walk_paths_rec::<V, F>(self.entry, &mut visited, false, 1, &mut on_path);
+    }
+
+    /// Depth-first walk from `node`, the `length`th cave of the path, marking small caves as
+    /// visited when entering them and unmarking them when backtracking so that checking a cave
+    /// does not scan the path
+    fn walk_paths_rec<V: VisitRule, F: FnMut(usize)>(
+        &self,
+        node: NodeIndex,
+        visited: &mut [bool],
+        visited_twice: bool,
+        length: usize,
+        on_path: &mut F,
+    ) {
+        if node == self.exit {
+            on_path(length);
+            return;
+        }
+
+        let mut edge_index = self.graph.nodes[node.0].edge;
+
+        while let Some(index) = edge_index {
+            let edge = &self.graph.edges[index.0];
+            let target = edge.target;
+
+            let length = length + 1;
+            match &self.graph.nodes[target.0].data {
+                Cave::Big(_) => {
+                    self.walk_paths_rec::<V, F>(target, visited, visited_twice, length, on_path)
+                }
+                _ if !visited[target.0] => {
+                    visited[target.0] = true;
+                    self.walk_paths_rec::<V, F>(target, visited, visited_twice, length, on_path);
+                    visited[target.0] = false;
+                }
+                // The cave stays marked since its first visit is still on the path
+                Cave::Small(_) if V::revisit(visited_twice) => {
+                    self.walk_paths_rec::<V, F>(target, visited, true, length, on_path)
+                }
+                _ => {}
+            }
+
+            // Follow the link to the next edge
+            edge_index = edge.next;
+        }
+    }
+
+    /// How many paths from the entry to the exit visit each number of caves
+    fn path_length_histogram<V: VisitRule>(&self) -> HashMap<usize, usize> {
+        let mut histogram = HashMap::new();
+        self.walk_paths::<V, _>(|length| *histogram.entry(length).or_insert(0) += 1);
+
+        histogram
+    }
+}
+
+/// How the paths of the cave system are walked, both giving the same counts
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Traversal {
+    /// Queue of paths, checking visits by scanning the path
+    Bfs,
+
+    /// Recursive walk over a visited set
+    Dfs,
+}
+
+impl FromStr for Traversal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bfs" => Ok(Traversal::Bfs),
+            "dfs" => Ok(Traversal::Dfs),
+            _ => Err(format!("Expected bfs or dfs traversal, got {:?}", s)),
+        }
+    }
+}
+
+/// Number of paths found between two progress reports
+const PROGRESS_PATHS: usize = 1000;
+
+/// Counts the paths found by a traversal, reporting every `PROGRESS_PATHS` of them
+struct PathCounter<'a> {
+    found: usize,
+
+    progress: Progress<'a>,
+}
+
+impl<'a> PathCounter<'a> {
+    fn new(progress: Progress<'a>) -> Self {
+        PathCounter { found: 0, progress }
+    }
+
+    fn found_path(&mut self) {
+        self.found += 1;
 
-        paths
+        if let Some(progress) = self.progress.as_mut() {
+            if self.found.is_multiple_of(PROGRESS_PATHS) {
+                progress(self.found);
+            }
+        }
     }
 }
 
-struct Day12;
+struct Day12 {
+    /// The traversal given on the command line, depth-first otherwise
+    traversal: Option<String>,
+
+    progress: bool,
+
+    /// Print how many paths visit each number of caves
+    verbose: bool,
+}
+
+impl Day12 {
+    fn traversal(&self) -> Result<Traversal, SolverError> {
+        match &self.traversal {
+            Some(traversal) => traversal
+                .parse()
+                .map_err(|e: String| SolverError::Generic(e.into())),
+            None => Ok(Traversal::Dfs),
+        }
+    }
+
+    fn solve<V: VisitRule>(&self, lines: Vec<String>) -> SolverResult {
+        let cave_system = CaveSystem::parse(lines).map_err(|e| SolverError::Generic(e.into()))?;
+
+        if self.verbose {
+            let mut histogram = cave_system
+                .path_length_histogram::<V>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            histogram.sort();
+
+            for (length, count) in histogram {
+                println!("{} paths visit {} caves", count, length);
+            }
+        }
+
+        let count = cave_system
+            .count_paths_with::<V>(self.traversal()?, super::stderr_progress(self.progress));
+        super::end_stderr_progress(self.progress);
+
+        Ok(count.to_string())
+    }
+}
 
 impl Solver for Day12 {
     fn name(&self) -> &'static str {
@@ -367,21 +554,11 @@ impl Solver for Day12 {
     }
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        let cave_system = CaveSystem::parse(lines).map_err(|e| SolverError::Generic(e.into()))?;
-        let paths = cave_system
-            .find_paths::<VisitBigMultipleSmallOnce>(cave_system.entry, cave_system.exit);
-
-        Ok(paths.len().to_string())
+        self.solve::<VisitBigMultipleSmallOnce>(lines)
     }
 
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        let cave_system = CaveSystem::parse(lines).map_err(|e| SolverError::Generic(e.into()))?;
-        let paths = cave_system.find_paths::<VisitBigMultipleSingleSmallTwiceOtherOnce>(
-            cave_system.entry,
-            cave_system.exit,
-        );
-
-        Ok(paths.len().to_string())
+        self.solve::<VisitBigMultipleSingleSmallTwiceOtherOnce>(lines)
     }
 
     fn test_expected(&self, part: usize) -> &'static str {
@@ -391,8 +568,182 @@ impl Solver for Day12 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.traversal = options.traversal.clone();
+        self.progress = options.progress;
+        self.verbose = options.verbose;
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day12)
+    Box::new(Day12 {
+        traversal: None,
+        progress: false,
+        verbose: false,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    const SMALL_SAMPLE: &str = "start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end";
+
+    const LARGER_SAMPLE: &str = "dc-end\nHN-start\nstart-kj\ndc-start\ndc-HN\nLN-dc\nHN-end\n\
+                                 kj-sa\nkj-HN\nkj-dc";
+
+    const LARGEST_SAMPLE: &str = "fs-end\nhe-DX\nfs-he\nstart-DX\npj-DX\nend-zg\nzg-sl\n\
+                                  zg-pj\npj-he\nRW-he\nfs-DX\npj-RW\nzg-RW\nstart-pj\nhe-WI\n\
+                                  zg-he\npj-fs\nstart-RW";
+
+    fn parse(sample: &str) -> CaveSystem {
+        CaveSystem::parse(sample.lines().map(String::from).collect()).unwrap()
+    }
+
+    #[test]
+    fn should_count_sample_paths() {
+        let samples = [
+            (SMALL_SAMPLE, 10, 36),
+            (LARGER_SAMPLE, 19, 103),
+            (LARGEST_SAMPLE, 226, 3509),
+        ];
+
+        for (sample, part1, part2) in samples {
+            let cave_system = parse(sample);
+
+            assert_eq!(
+                cave_system.count_paths::<VisitBigMultipleSmallOnce>(None),
+                part1
+            );
+            assert_eq!(
+                cave_system.count_paths::<VisitBigMultipleSingleSmallTwiceOtherOnce>(None),
+                part2
+            );
+        }
+    }
+
+    /// Compare the depth-first count to the scanning traversal, run with
+    /// `cargo test --release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_count_paths_against_scanning() {
+        use std::time::Instant;
+
+        type V = VisitBigMultipleSingleSmallTwiceOtherOnce;
+
+        const RUNS: usize = 50;
+        let cave_system = parse(LARGEST_SAMPLE);
+
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            assert_eq!(cave_system.count_paths_bfs::<V>(None), 3509);
+        }
+        let scanning = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            assert_eq!(cave_system.count_paths::<V>(None), 3509);
+        }
+        let dfs = start.elapsed();
+
+        println!("scanning: {:?}, visited set: {:?}", scanning, dfs);
+        assert!(dfs < scanning);
+    }
+
+    #[test]
+    fn should_count_same_paths_with_both_traversals() {
+        let cave_system = parse(LARGEST_SAMPLE);
+
+        for traversal in ["bfs", "dfs"] {
+            let traversal = traversal.parse().unwrap();
+
+            assert_eq!(
+                cave_system.count_paths_with::<VisitBigMultipleSmallOnce>(traversal, None),
+                226
+            );
+            assert_eq!(
+                cave_system
+                    .count_paths_with::<VisitBigMultipleSingleSmallTwiceOtherOnce>(traversal, None),
+                3509
+            );
+        }
+
+        assert!("bfd".parse::<Traversal>().is_err());
+    }
+
+    #[test]
+    fn should_count_small_sample_paths_by_length() {
+        let lines = SMALL_SAMPLE.lines().map(String::from).collect();
+        let cave_system = CaveSystem::parse(lines).unwrap();
+
+        let histogram = cave_system.path_length_histogram::<VisitBigMultipleSmallOnce>();
+        let expected = HashMap::from([(3, 2), (4, 2), (5, 2), (6, 2), (7, 2)]);
+
+        assert_eq!(histogram, expected);
+        assert_eq!(histogram.values().sum::<usize>(), 10);
+    }
+
+    thread_local! {
+        /// How many times `WatchedVisitRule` was asked about a cave on this thread
+        static VISIT_CHECKS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// The rule of the second part, counting how far a traversal went
+    struct WatchedVisitRule;
+
+    impl VisitRule for WatchedVisitRule {
+        type Path = <VisitBigMultipleSingleSmallTwiceOtherOnce as VisitRule>::Path;
+
+        fn visit(
+            graph: &Graph,
+            current_path: &Self::Path,
+            node_index: NodeIndex,
+            node: &Node,
+        ) -> Option<Self::Path> {
+            VISIT_CHECKS.with(|checks| checks.set(checks.get() + 1));
+            VisitBigMultipleSingleSmallTwiceOtherOnce::visit(graph, current_path, node_index, node)
+        }
+
+        fn create_path(path: Vec<NodeIndex>) -> Self::Path {
+            VisitBigMultipleSingleSmallTwiceOtherOnce::create_path(path)
+        }
+
+        fn last(path: &Self::Path) -> NodeIndex {
+            VisitBigMultipleSingleSmallTwiceOtherOnce::last(path)
+        }
+
+        fn revisit(visited_twice: bool) -> bool {
+            VISIT_CHECKS.with(|checks| checks.set(checks.get() + 1));
+            VisitBigMultipleSingleSmallTwiceOtherOnce::revisit(visited_twice)
+        }
+    }
+
+    #[test]
+    fn should_report_progress_of_both_traversals() {
+        let cave_system = parse(LARGEST_SAMPLE);
+        let checks = || VISIT_CHECKS.with(Cell::get);
+
+        for traversal in [Traversal::Bfs, Traversal::Dfs] {
+            // The paths found and the caves checked so far, at each report
+            let mut reports = Vec::new();
+            let count = cave_system.count_paths_with::<WatchedVisitRule>(
+                traversal,
+                Some(Box::new(|found| reports.push((found, checks())))),
+            );
+
+            assert_eq!(count, 3509);
+            assert_eq!(
+                reports.iter().map(|&(found, _)| found).collect::<Vec<_>>(),
+                (1..=3509 / PROGRESS_PATHS)
+                    .map(|i| i * PROGRESS_PATHS)
+                    .collect::<Vec<_>>()
+            );
+
+            // The reports come in as the traversal goes, not all at once once it is over
+            assert!(reports.windows(2).all(|w| w[0].1 < w[1].1));
+            assert!(reports.last().unwrap().1 < checks());
+        }
+    }
 }
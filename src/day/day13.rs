@@ -1,3 +1,5 @@
+use super::grid::{BoundingBox, GridParseError};
+use super::parse::SectionParser;
 use super::{Solver, SolverError, SolverResult};
 use std::fmt::{self, Write};
 
@@ -16,7 +18,7 @@ impl FromStr for FoldInstruction {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const PREFIX: &'static str = "fold along ";
+        const PREFIX: &str = "fold along ";
 
         if !s.starts_with(PREFIX) {
             return Err("Invalid fold instruction");
@@ -100,6 +102,34 @@ enum Point {
     Invisible,
 }
 
+/// Width of a letter drawn by the dots, the letters being separated by an empty column
+const GLYPH_WIDTH: usize = 4;
+
+/// Height of a letter drawn by the dots
+const GLYPH_HEIGHT: usize = 6;
+
+/// The letters the dots may draw, row by row
+const FONT: &[(char, [&str; GLYPH_HEIGHT])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...", "#...", ".#.#", "..#.", "..#.", "..#."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
 #[derive(Debug)]
 struct Grid {
     points: Vec<Point>,
@@ -155,63 +185,86 @@ impl Grid {
         folded_grid
     }
 
-    fn parse(lines: Vec<String>) -> Result<(Grid, Vec<FoldInstruction>), SolverError> {
-        enum ParsingState {
-            Coord,
-            FoldInstruction,
+    fn is_dot(&self, x: usize, y: usize) -> bool {
+        self.points[y + x * self.height] == Point::Dot
+    }
+
+    /// Read the letters drawn by the dots, `None` if the grid does not only contain known letters
+    fn decode(&self) -> Option<String> {
+        if self.height != GLYPH_HEIGHT || self.width < GLYPH_WIDTH {
+            return None;
         }
 
-        let mut state = ParsingState::Coord;
+        (0..self.width)
+            .step_by(GLYPH_WIDTH + 1)
+            .map(|left| {
+                // The gap column after the last letter may have been folded away
+                let columns = left..(left + GLYPH_WIDTH + 1).min(self.width);
+                if columns.len() < GLYPH_WIDTH {
+                    return None;
+                }
 
-        let mut coords = Vec::new();
-        let mut instructions = Vec::new();
+                let (letter, _) = FONT.iter().find(|(_, glyph)| {
+                    glyph.iter().enumerate().all(|(y, row)| {
+                        let row = format!("{}.", row);
+                        columns
+                            .clone()
+                            .zip(row.chars())
+                            .all(|(x, c)| self.is_dot(x, y) == (c == '#'))
+                    })
+                })?;
+
+                Some(*letter)
+            })
+            .collect()
+    }
 
-        let mut max_x = 0u64;
-        let mut max_y = 0u64;
+    fn parse(lines: Vec<String>) -> Result<(Grid, Vec<FoldInstruction>), SolverError> {
+        let mut sections = SectionParser::new(&lines);
 
-        for line in lines {
-            if line.is_empty() {
-                state = ParsingState::FoldInstruction;
-                continue;
-            }
+        let mut bounds = BoundingBox::default();
 
-            match state {
-                ParsingState::Coord => {
-                    let mut split = line.split(",");
-
-                    let x = split
-                        .next()
-                        .ok_or(SolverError::Generic("Missing x coordinate".into()))?;
-                    let y = split
-                        .next()
-                        .ok_or(SolverError::Generic("Missing y coordinate".into()))?;
-
-                    let x = x
-                        .parse::<u64>()
-                        .map_err(|e| SolverError::Generic(e.into()))?;
-                    let y = y
-                        .parse::<u64>()
-                        .map_err(|e| SolverError::Generic(e.into()))?;
-
-                    if x > max_x {
-                        max_x = x;
-                    }
+        let coords = sections.section("coordinates", |lines| {
+            let mut coords = Vec::new();
 
-                    if y > max_y {
-                        max_y = y;
-                    }
+            for line in lines {
+                let mut split = line.split(',');
 
-                    coords.push((x, y));
-                }
-                ParsingState::FoldInstruction => {
-                    let instruction = FoldInstruction::from_str(line.as_str())
-                        .map_err(|e| SolverError::Generic(e.into()))?;
-                    instructions.push(instruction)
-                }
-            };
+                let x = split
+                    .next()
+                    .ok_or(SolverError::Generic("Missing x coordinate".into()))?;
+                let y = split
+                    .next()
+                    .ok_or(SolverError::Generic("Missing y coordinate".into()))?;
+
+                let x = x
+                    .parse::<u64>()
+                    .map_err(|e| SolverError::Generic(e.into()))?;
+                let y = y
+                    .parse::<u64>()
+                    .map_err(|e| SolverError::Generic(e.into()))?;
+
+                bounds.extend((x as usize, y as usize));
+                coords.push((x, y));
+            }
+
+            Ok(coords)
+        })?;
+
+        if coords.is_empty() {
+            return Err(GridParseError::Empty.into());
         }
 
-        let mut grid = Grid::with_capacity(max_x as usize + 1, max_y as usize + 1);
+        let instructions = sections.section("fold instructions", |lines| {
+            lines
+                .into_iter()
+                .map(FoldInstruction::from_str)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| SolverError::Generic(e.into()))
+        })?;
+
+        let (width, height) = bounds.dimensions();
+        let mut grid = Grid::with_capacity(width, height);
 
         for coord in coords {
             grid.add(coord.0 as usize, coord.1 as usize)
@@ -234,7 +287,7 @@ impl fmt::Display for Grid {
                     Point::Invisible => f.write_char('.')?,
                 }
             }
-            print!("\n");
+            f.write_char('\n')?;
         }
 
         Ok(())
@@ -251,7 +304,7 @@ impl Solver for Day13 {
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
         let (grid, instructions) = Grid::parse(lines)?;
         let first_instruction = instructions
-            .get(0)
+            .first()
             .ok_or("Empty fold instructions")
             .map_err(|e| SolverError::Generic(e.into()))?;
 
@@ -270,14 +323,14 @@ impl Solver for Day13 {
             grid = grid.apply(instruction);
         }
 
-        println!("{}", grid);
-        Ok("".to_string())
+        // Fall back to the dots themselves when they do not draw known letters
+        Ok(grid.decode().unwrap_or_else(|| grid.to_string()))
     }
 
     fn test_expected(&self, part: usize) -> &'static str {
         match part {
             1 => "17",
-            2 => "",
+            2 => "#####\n#...#\n#...#\n#...#\n#####\n.....\n.....",
             _ => unreachable!(),
         }
     }
@@ -286,3 +339,54 @@ impl Solver for Day13 {
 pub(super) fn new() -> Box<dyn Solver> {
     Box::new(Day13)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str =
+        "6,10\n0,14\n9,10\n0,3\n10,4\n4,11\n6,0\n6,12\n4,1\n0,13\n10,12\n3,4\n3,0\n\
+        8,4\n1,10\n2,14\n8,10\n9,0\n\nfold along y=7\nfold along x=5";
+
+    #[test]
+    fn should_format_folded_grid_rows() {
+        let (mut grid, instructions) =
+            Grid::parse(SAMPLE.lines().map(String::from).collect()).unwrap();
+        for instruction in instructions {
+            grid = grid.apply(instruction);
+        }
+
+        assert_eq!(
+            format!("{}", grid),
+            "#####\n#...#\n#...#\n#...#\n#####\n.....\n.....\n"
+        );
+        assert_eq!(grid.decode(), None);
+    }
+
+    #[test]
+    fn should_decode_letters_drawn_by_dots() {
+        let rows = [
+            "#..#.####.###..",
+            "#..#.#....#..#.",
+            "####.###..###..",
+            "#..#.#....#..#.",
+            "#..#.#....#..#.",
+            "#..#.####.###..",
+        ];
+
+        let mut grid = Grid::with_capacity(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    grid.add(x, y);
+                }
+            }
+        }
+
+        assert_eq!(grid.decode(), Some("HEB".to_string()));
+
+        // An unknown glyph fails the whole decoding
+        grid.add(1, 1);
+        assert_eq!(grid.decode(), None);
+    }
+}
@@ -1,98 +1,213 @@
-use super::{Solver, SolverError, SolverResult};
+use super::parse::{parse_pair, SectionParser};
+use super::stats::Counter;
+use super::{Options, Solver, SolverError, SolverResult};
 
-use std::collections::HashMap;
-use std::fmt;
+use std::collections::{HashMap, HashSet};
 use std::result::Result;
 
-#[derive(Debug)]
-enum PairInsertionError {
-    MissingPair,
+fn parse_insertion_pair(s: &str) -> Result<(String, String), SolverError> {
+    let (pair, insertion) = parse_pair(s, " -> ")?;
 
-    MissingInsertion,
+    Ok((pair.to_string(), insertion.to_string()))
 }
 
-impl std::error::Error for PairInsertionError {}
+type Rules = HashMap<String, String>;
 
-impl fmt::Display for PairInsertionError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
-    }
+type PairsTable = HashMap<String, u64>;
+
+fn parse(lines: &[String]) -> Result<(&str, Rules), SolverError> {
+    let mut sections = SectionParser::new(lines);
+
+    let template = sections.section("polymer template", |lines| {
+        lines.first().copied().ok_or(SolverError::Generic(
+            "Failed to retrieve the polymer template".into(),
+        ))
+    })?;
+
+    let rules = sections.section("pair insertion rules", |lines| {
+        lines
+            .into_iter()
+            .map(parse_insertion_pair)
+            .collect::<Result<HashMap<_, _>, _>>()
+    })?;
+
+    Ok((template, rules))
 }
 
-fn parse_insertion_pair(s: &str) -> Result<(String, String), PairInsertionError> {
-    let mut split = s.split(" -> ");
+fn pairs_table(template: &str) -> PairsTable {
+    let mut pairs_table = HashMap::new();
+    let mut index = 0usize;
+    while let Some(pair) = template.get(index..index + 2) {
+        *pairs_table.entry(pair.to_string()).or_insert(0) += 1;
 
-    let pair = split.next().ok_or(PairInsertionError::MissingPair)?;
-    let insertion = split.next().ok_or(PairInsertionError::MissingInsertion)?;
+        index += 1;
+    }
 
-    Ok((pair.to_string(), insertion.to_string()))
+    pairs_table
 }
 
-fn solve(lines: Vec<String>, steps: usize) -> SolverResult {
-    let template = lines.get(0).ok_or(SolverError::Generic(
-        "Failed to retrieve the polymer template".into(),
-    ))?;
+fn step(pairs_table: PairsTable, rules: &Rules) -> PairsTable {
+    let mut new_pairs_table = HashMap::new();
+    for (pair, count) in pairs_table {
+        if let Some(insertion) = rules.get(&pair) {
+            let pair_bytes = pair.as_bytes();
 
-    let insertions = lines.get(2..).ok_or(SolverError::Generic(
-        "Failed to retrieve pair insertion rules".into(),
-    ))?;
+            let insertion_bytes = insertion.as_bytes();
+            let insertion = insertion_bytes[0];
 
-    let insertion_pairs = insertions
-        .iter()
-        .map(|l| parse_insertion_pair(&l))
-        .collect::<Result<HashMap<_, _>, _>>()
-        .map_err(|e| SolverError::Generic(e.into()))?;
+            let pair_left = String::from_utf8_lossy(&[pair_bytes[0], insertion]).to_string();
+            let pair_right = String::from_utf8_lossy(&[insertion, pair_bytes[1]]).to_string();
 
-    let mut pairs_table = HashMap::new();
-    let mut index = 0usize;
-    while let Some(pair) = template.as_str().get(index..index + 2) {
-        if let Some(count) = pairs_table.get_mut(pair) {
-            *count += 1;
+            *new_pairs_table.entry(pair_left).or_insert(0) += count;
+            *new_pairs_table.entry(pair_right).or_insert(0) += count;
         } else {
-            pairs_table.insert(pair.to_string(), 1);
+            *new_pairs_table.entry(pair).or_insert(0) += count;
         }
+    }
 
-        index += 1;
+    new_pairs_table
+}
+
+/// The length of a polymer whose overlapping pairs are counted in `pairs_table`
+fn polymer_length(pairs_table: &PairsTable) -> u64 {
+    pairs_table.values().sum::<u64>() + 1
+}
+
+/// The length of the polymer after `steps` steps, computed from the pairs table
+fn length_after(template: &str, rules: &Rules, steps: usize) -> u64 {
+    let pairs_table = (0..steps).fold(pairs_table(template), |table, _| step(table, rules));
+    polymer_length(&pairs_table)
+}
+
+/// Longest polymer `expand` agrees to build, longer ones only being counted through pairs
+const MAX_EXPANDED_LENGTH: u64 = 1 << 20;
+
+/// Longest polymer printed as is in verbose mode, only the length of longer ones being printed
+const MAX_PRINTED_LENGTH: u64 = 80;
+
+/// The actual polymer after `steps` steps, for small step counts. Fails when the polymer would
+/// be longer than `MAX_EXPANDED_LENGTH`
+fn expand(template: &str, rules: &Rules, steps: usize) -> Result<String, SolverError> {
+    let length = length_after(template, rules, steps);
+    if length > MAX_EXPANDED_LENGTH {
+        return Err(SolverError::Generic(
+            format!(
+                "Polymer of {} elements after {} steps is too long to expand",
+                length, steps
+            )
+            .into(),
+        ));
     }
 
+    let mut polymer = template.to_string();
     for _ in 0..steps {
-        let mut new_pairs_table = HashMap::new();
-        for (pair, count) in pairs_table {
-            if let Some(insertion) = insertion_pairs.get(&pair) {
-                let pair_bytes = pair.as_bytes();
+        let elements = polymer.chars().collect::<Vec<_>>();
 
-                let insertion_bytes = insertion.as_bytes();
-                let insertion = insertion_bytes[0];
+        // Keep the first element, then insert between each pair before its second element
+        let mut expanded = elements.iter().take(1).collect::<String>();
+        for pair in elements.windows(2) {
+            if let Some(insertion) = rules.get(&pair.iter().collect::<String>()) {
+                expanded.push_str(insertion);
+            }
+            expanded.push(pair[1]);
+        }
 
-                let pair_left = String::from_utf8_lossy(&[pair_bytes[0], insertion]).to_string();
-                let pair_right = String::from_utf8_lossy(&[insertion, pair_bytes[1]]).to_string();
+        polymer = expanded;
+    }
 
-                *new_pairs_table.entry(pair_left).or_insert(0) += count;
-                *new_pairs_table.entry(pair_right).or_insert(0) += count;
-            } else {
-                *new_pairs_table.entry(pair).or_insert(0) += count;
+    Ok(polymer)
+}
+
+/// The pairs that can appear in the polymer grown from `template` but have no insertion rule, so
+/// are left unexpanded by every step
+fn validate_rules(template: &str, rules: &Rules) -> HashSet<String> {
+    let mut missing = HashSet::new();
+
+    let mut reached = pairs_table(template).into_keys().collect::<HashSet<_>>();
+    let mut pending = reached.iter().cloned().collect::<Vec<_>>();
+
+    while let Some(pair) = pending.pop() {
+        let insertion = match rules.get(&pair).and_then(|i| i.chars().next()) {
+            Some(insertion) => insertion,
+            None => {
+                missing.insert(pair);
+                continue;
             }
-        }
+        };
+
+        let mut chars = pair.chars();
+        let (left, right) = (chars.next().unwrap(), chars.next().unwrap());
 
-        pairs_table = new_pairs_table;
+        for produced in [
+            format!("{}{}", left, insertion),
+            format!("{}{}", insertion, right),
+        ] {
+            if reached.insert(produced.clone()) {
+                pending.push(produced);
+            }
+        }
     }
 
-    let mut occurences = HashMap::new();
+    missing
+}
+
+/// Count the elements of the polymer grown from `template`. Every element is the first one of a
+/// pair, except for the last element of the template which always stays last
+fn element_counts(template: &str, pairs_table: &PairsTable) -> Counter<char> {
+    let mut occurences = Counter::new();
     for (pair, count) in pairs_table {
         let first_char = pair.chars().next().unwrap();
-        *occurences.entry(first_char).or_insert(0usize) += count;
+        occurences.add(first_char, *count);
     }
 
-    let mut count = occurences.into_iter().collect::<Vec<_>>();
-    count.sort_by(|a, b| a.1.cmp(&b.1));
+    occurences.extend(template.chars().last());
 
-    let least_common = count.first().expect("Should have at least one element");
-    let most_common = count.last().expect("Should have at least one element");
+    occurences
+}
+
+fn solve(lines: Vec<String>, steps: usize, verbose: bool) -> SolverResult {
+    let (template, rules) = parse(&lines)?;
+
+    if verbose {
+        let mut missing = validate_rules(template, &rules)
+            .into_iter()
+            .collect::<Vec<_>>();
+        missing.sort();
+
+        for pair in missing {
+            println!("Warning: pair {} has no insertion rule", pair);
+        }
+    }
+
+    let mut pairs_table = pairs_table(template);
+    for i in 1..=steps {
+        pairs_table = step(pairs_table, &rules);
 
-    Ok(((most_common.1 - least_common.1) + 1).to_string())
+        if verbose {
+            let length = polymer_length(&pairs_table);
+            if length <= MAX_PRINTED_LENGTH {
+                println!("After step {}: {}", i, expand(template, &rules, i)?);
+            } else {
+                println!("After step {}: length {}", i, length);
+            }
+        }
+    }
+
+    let occurences = element_counts(template, &pairs_table);
+
+    let least_common = occurences
+        .least_common()
+        .expect("Should have at least one element");
+    let most_common = occurences
+        .most_common()
+        .expect("Should have at least one element");
+
+    Ok((most_common.1 - least_common.1).to_string())
 }
 
-struct Day14;
+struct Day14 {
+    verbose: bool,
+}
 
 impl Solver for Day14 {
     fn name(&self) -> &'static str {
@@ -100,11 +215,11 @@ impl Solver for Day14 {
     }
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        solve(lines, 10)
+        solve(lines, 10, self.verbose)
     }
 
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        solve(lines, 40)
+        solve(lines, 40, self.verbose)
     }
 
     fn test_expected(&self, part: usize) -> &'static str {
@@ -114,8 +229,95 @@ impl Solver for Day14 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.verbose = options.verbose;
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day14)
+    Box::new(Day14 { verbose: false })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = "NNCB
+
+CH -> B
+HH -> N
+CB -> H
+NH -> C
+HB -> C
+HC -> B
+HN -> C
+NN -> C
+BH -> H
+NC -> B
+NB -> B
+BN -> B
+BB -> N
+BC -> B
+CC -> N
+CN -> C";
+
+    #[test]
+    fn should_compute_sample_lengths() {
+        let lines = SAMPLE.lines().map(String::from).collect::<Vec<_>>();
+        let (template, rules) = parse(&lines).unwrap();
+
+        let lengths = (1..=4)
+            .map(|steps| length_after(template, &rules, steps))
+            .collect::<Vec<_>>();
+
+        assert_eq!(lengths, vec![7, 13, 25, 49]);
+    }
+
+    #[test]
+    fn should_count_elements_of_sample_polymer() {
+        let lines = SAMPLE.lines().map(String::from).collect::<Vec<_>>();
+        let (template, rules) = parse(&lines).unwrap();
+
+        let polymer = expand(template, &rules, 4).unwrap();
+        let pairs_table = (0..4).fold(pairs_table(template), |table, _| step(table, &rules));
+        let counts = element_counts(template, &pairs_table);
+
+        let mut expected = Counter::new();
+        expected.extend(polymer.chars());
+
+        assert_eq!(counts.most_common(), expected.most_common());
+        assert_eq!(counts.least_common(), expected.least_common());
+
+        assert_eq!(solve(lines.clone(), 10, false).unwrap(), "1588");
+        assert_eq!(solve(lines, 40, false).unwrap(), "2188189693529");
+    }
+
+    #[test]
+    fn should_report_reachable_pairs_without_rule() {
+        let lines = SAMPLE.lines().map(String::from).collect::<Vec<_>>();
+        let (template, mut rules) = parse(&lines).unwrap();
+
+        assert!(validate_rules(template, &rules).is_empty());
+
+        // BC is not in the template, it only appears once NC gets expanded
+        rules.remove("BC");
+        assert_eq!(
+            validate_rules(template, &rules),
+            HashSet::from(["BC".to_string()])
+        );
+    }
+
+    #[test]
+    fn should_expand_sample_polymer() {
+        let lines = SAMPLE.lines().map(String::from).collect::<Vec<_>>();
+        let (template, rules) = parse(&lines).unwrap();
+
+        assert_eq!(expand(template, &rules, 1).unwrap(), "NCNBCHB");
+        assert_eq!(
+            expand(template, &rules, 4).unwrap(),
+            "NBBNBNBBCCNBCNCCNBBNBBNBBBNBBNBBCBHCBHHNHCBBCBHCB"
+        );
+        assert!(expand(template, &rules, 40).is_err());
+    }
 }
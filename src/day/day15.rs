@@ -4,15 +4,15 @@ struct Day15;
 
 impl Solver for Day15 {
     fn name(&self) -> &'static str {
-        todo!();
+        "Chiton"
     }
 
-    fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        todo!();
+    fn solve_part1(&self, _lines: Vec<String>) -> SolverResult {
+        Err(SolverError::Unimplemented(15))
     }
 
-    fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        todo!();
+    fn solve_part2(&self, _lines: Vec<String>) -> SolverResult {
+        Err(SolverError::Unimplemented(15))
     }
 
     fn test_expected(&self, part: usize) -> &'static str {
@@ -22,6 +22,10 @@ impl Solver for Day15 {
             _ => unreachable!(),
         }
     }
+
+    fn implemented(&self) -> bool {
+        false
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
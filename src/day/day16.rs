@@ -1,6 +1,6 @@
-use super::{Solver, SolverError, SolverResult};
+use super::{Options, Solver, SolverError, SolverResult};
 
-use std::collections::VecDeque;
+use bits::Packet;
 
 mod hex {
     use std::num::ParseIntError;
@@ -14,6 +14,10 @@ mod hex {
 }
 
 mod bits {
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::fmt;
+
     #[derive(Debug, Eq, PartialEq)]
     pub(super) struct Version(u8);
 
@@ -53,14 +57,78 @@ mod bits {
     }
 
     impl<'a> BitReader<'a> {
-        pub fn new(buf: &[u8], start_offset: usize) -> BitReader {
+        pub fn new(buf: &'a [u8], start_offset: usize) -> BitReader<'a> {
             BitReader {
                 buf,
                 offset: start_offset,
             }
         }
 
+        /// The offset of the next bit to read
+        pub fn offset(&self) -> usize {
+            self.offset
+        }
+
+        /// Whether every bit left to read is 0, like the padding ending a transmission
+        pub fn only_zeros_left(&self) -> bool {
+            let mut reader = BitReader::new(self.buf, self.offset);
+
+            // The bits up to the next byte boundary, then the whole bytes that follow
+            let unaligned = (u8::BITS as usize - self.offset % u8::BITS as usize) % 8;
+            let partial = match unaligned {
+                0 => 0,
+                _ => reader.consume::<u8>(unaligned).unwrap_or(0),
+            };
+            let bytes_left = self
+                .buf
+                .len()
+                .saturating_sub(reader.offset / u8::BITS as usize);
+
+            partial == 0
+                && reader
+                    .consume_bytes(bytes_left)
+                    .is_some_and(|bytes| bytes.iter().all(|&b| b == 0))
+        }
+
+        /// Read the next `count` bits and advance past them
         pub fn consume<T: Primitive>(&mut self, count: usize) -> Option<T> {
+            let (result, offset) = self.read_bits(count)?;
+
+            self.offset = offset;
+            Some(result)
+        }
+
+        /// Read the next `count` bits without advancing
+        pub fn peek<T: Primitive>(&self, count: usize) -> Option<T> {
+            self.read_bits(count).map(|(result, _)| result)
+        }
+
+        /// Read the next `n` whole bytes and advance past them. They are borrowed from the buffer
+        /// when the reader is byte-aligned, and read bit by bit otherwise
+        pub fn consume_bytes(&mut self, n: usize) -> Option<Cow<'a, [u8]>> {
+            if self.offset.is_multiple_of(u8::BITS as usize) {
+                let start = self.offset / u8::BITS as usize;
+                let bytes = self.buf.get(start..start + n)?;
+
+                self.offset += n * u8::BITS as usize;
+                return Some(Cow::Borrowed(bytes));
+            }
+
+            // Only advance once all the bytes could be read
+            let mut reader = BitReader {
+                buf: self.buf,
+                offset: self.offset,
+            };
+            let bytes = (0..n)
+                .map(|_| reader.consume::<u8>(u8::BITS as usize))
+                .collect::<Option<Vec<_>>>()?;
+
+            self.offset = reader.offset;
+            Some(Cow::Owned(bytes))
+        }
+
+        /// Read `count` bits from the current offset, returning them with the offset that follows
+        fn read_bits<T: Primitive>(&self, count: usize) -> Option<(T, usize)> {
             if count > T::BITS {
                 return None;
             }
@@ -94,8 +162,7 @@ mod bits {
                 result = result.shl(shift_by as u32);
             }
 
-            self.offset = offset;
-            Some(result)
+            Some((result, offset))
         }
 
         fn get_byte(&self, bit_offset: usize) -> Option<&u8> {
@@ -174,6 +241,9 @@ mod bits {
         version: Version,
 
         kind: PacketKind,
+
+        /// The number of bits the packet and its sub-packets occupied in the transmission
+        bit_length: usize,
     }
 
     impl Packet {
@@ -181,6 +251,29 @@ mod bits {
             self.version.0
         }
 
+        pub fn bit_length(&self) -> usize {
+            self.bit_length
+        }
+
+        pub fn type_id(&self) -> u8 {
+            let type_id = match &self.kind {
+                PacketKind::Sum(_) => PACKET_SUM,
+                PacketKind::Product(_) => PACKET_PRODUCT,
+                PacketKind::Minimum(_) => PACKET_MINIMUM,
+                PacketKind::Maximum(_) => PACKET_MAXIMUM,
+                PacketKind::Literal(_) => PACKET_LITERAL,
+                PacketKind::Greater(_) => PACKET_GT,
+                PacketKind::Less(_) => PACKET_LT,
+                PacketKind::Equal(_) => PACKET_EQ,
+            };
+
+            type_id.0
+        }
+
+        pub fn is_literal(&self) -> bool {
+            matches!(self.kind, PacketKind::Literal(_))
+        }
+
         pub fn sub_packets(&self) -> Option<&Vec<Packet>> {
             match &self.kind {
                 PacketKind::Sum(packets)
@@ -194,6 +287,60 @@ mod bits {
             }
         }
 
+        /// Iterate over this packet and all of its sub-packets, depth first
+        pub fn iter(&self) -> Iter<'_> {
+            Iter { stack: vec![self] }
+        }
+
+        /// Count how many packets of each type id appear in this packet tree
+        pub fn type_histogram(&self) -> HashMap<u8, usize> {
+            let mut histogram = HashMap::new();
+
+            for packet in self.iter() {
+                *histogram.entry(packet.type_id()).or_insert(0) += 1;
+            }
+
+            histogram
+        }
+
+        /// Every literal value of this packet tree, depth first
+        pub fn literals(&self) -> Vec<u64> {
+            self.iter()
+                .filter(|packet| packet.is_literal())
+                .map(Self::eval)
+                .collect()
+        }
+
+        /// The operation of the packet, as rendered by `pretty`
+        fn operation(&self) -> &'static str {
+            match &self.kind {
+                PacketKind::Sum(_) => "sum",
+                PacketKind::Product(_) => "product",
+                PacketKind::Minimum(_) => "minimum",
+                PacketKind::Maximum(_) => "maximum",
+                PacketKind::Literal(_) => "literal",
+                PacketKind::Greater(_) => "greater",
+                PacketKind::Less(_) => "less",
+                PacketKind::Equal(_) => "equal",
+            }
+        }
+
+        /// Render this packet tree with one packet per line, sub-packets being indented by two
+        /// more spaces than their parent, starting at `indent`
+        pub fn pretty(&self, indent: usize) -> String {
+            let mut pretty = format!("{:indent$}v{} {}", "", self.version(), self.operation());
+            if let PacketKind::Literal(lit) = &self.kind {
+                pretty.push_str(&format!(" {}", lit.0));
+            }
+            pretty.push('\n');
+
+            for packet in self.sub_packets().into_iter().flatten() {
+                pretty.push_str(&packet.pretty(indent + 2));
+            }
+
+            pretty
+        }
+
         pub fn eval(&self) -> u64 {
             match &self.kind {
                 PacketKind::Sum(packets) => packets.iter().map(Self::eval).sum(),
@@ -244,52 +391,135 @@ mod bits {
     const PACKET_LT: TypeId = TypeId(6);
     const PACKET_EQ: TypeId = TypeId(7);
 
-    pub fn decode(bytes: &[u8]) -> Vec<Packet> {
+    /// A depth-first iterator over a packet tree
+    pub struct Iter<'a> {
+        stack: Vec<&'a Packet>,
+    }
+
+    impl<'a> Iterator for Iter<'a> {
+        type Item = &'a Packet;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let packet = self.stack.pop()?;
+
+            if let Some(sub_packets) = packet.sub_packets() {
+                // Push in reverse so that the first sub-packet is visited first
+                self.stack.extend(sub_packets.iter().rev());
+            }
+
+            Some(packet)
+        }
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    pub enum DecodeError {
+        /// The transmission ended in the middle of a packet
+        UnexpectedEnd,
+
+        /// A literal value could not be decoded
+        InvalidLiteral,
+
+        /// A comparison packet does not have exactly two sub-packets
+        InvalidArity { type_id: u8, count: usize },
+
+        /// The transmission is too short to hold even a single packet
+        TooShort { bits: usize },
+    }
+
+    impl fmt::Display for DecodeError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                DecodeError::InvalidArity { type_id, count } => write!(
+                    f,
+                    "Comparison packet of type {} has {} sub-packets, expected 2",
+                    type_id, count
+                ),
+                DecodeError::TooShort { bits } => write!(
+                    f,
+                    "Transmission of {} bits is shorter than the {} bits of the smallest packet",
+                    bits, MIN_PACKET_BITS
+                ),
+                _ => write!(f, "{:?}", self),
+            }
+        }
+    }
+
+    impl std::error::Error for DecodeError {}
+
+    /// The smallest packet is a literal: version, type and a single group of 5 bits
+    const MIN_PACKET_BITS: usize = 3 + 3 + 5;
+
+    pub fn decode(bytes: &[u8]) -> Result<Vec<Packet>, DecodeError> {
+        let bits = bytes.len() * 8;
+        if bits < MIN_PACKET_BITS {
+            return Err(DecodeError::TooShort { bits });
+        }
+
         let mut packets = Vec::new();
-        let mut reader = BitReader::new(bytes, 0);
+        let mut offset = 0;
 
-        while let Some(packet) = decode_packet(&mut reader) {
-            packets.push(packet)
+        // The zeros padding the transmission would otherwise decode as bogus packets, while
+        // running out of bits anywhere else means the transmission was cut short
+        while !BitReader::new(bytes, offset).only_zeros_left() {
+            let packet = decode_packet(&mut BitReader::new(bytes, offset))?;
+
+            // The next packet starts right where this one ends
+            offset += packet.bit_length();
+            packets.push(packet);
         }
 
-        packets
+        Ok(packets)
     }
 
-    fn decode_packet(reader: &mut BitReader<'_>) -> Option<Packet> {
-        let version = Version::decode(reader)?;
-        let type_id = TypeId::decode(reader)?;
+    fn decode_packet(reader: &mut BitReader<'_>) -> Result<Packet, DecodeError> {
+        let start_offset = reader.offset();
+        let version = Version::decode(reader).ok_or(DecodeError::UnexpectedEnd)?;
+        let type_id = TypeId::decode(reader).ok_or(DecodeError::UnexpectedEnd)?;
 
         match type_id {
             PACKET_LITERAL => {
-                let literal = Varint::decode(reader)?;
-                Some(Packet {
+                let literal = Varint::decode(reader).ok_or(DecodeError::InvalidLiteral)?;
+                Ok(Packet {
                     version,
                     kind: PacketKind::Literal(literal),
+                    bit_length: reader.offset() - start_offset,
                 })
             }
             _ => {
-                let length_type_id: u8 = reader.consume(1)?;
+                // The length type id tells how the sub-packets are delimited. It is then read as
+                // the top bit of the length that follows: a 0 leaves the total bit length as is,
+                // while a 1 gets masked off the packet count
+                let length_type_id: u8 = reader.peek(1).ok_or(DecodeError::UnexpectedEnd)?;
                 let packets = if length_type_id == 0 {
-                    let total_bits: u16 = reader.consume(15)?;
-                    let end_offset = reader.offset + total_bits as usize;
+                    let total_bits: u16 =
+                        reader.consume(1 + 15).ok_or(DecodeError::UnexpectedEnd)?;
+                    let end_offset = reader.offset() + total_bits as usize;
 
                     let mut packets = Vec::new();
-                    while reader.offset < end_offset {
+                    while reader.offset() < end_offset {
                         packets.push(decode_packet(reader)?);
                     }
 
-                    packets
-                } else if length_type_id == 1 {
-                    let packets_count: u16 = reader.consume(11)?;
-                    let packets = (0..packets_count)
-                        .map(|_| decode_packet(reader))
-                        .collect::<Option<Vec<_>>>()?;
-
                     packets
                 } else {
-                    unreachable!();
+                    let packets_count = reader
+                        .consume::<u16>(1 + 11)
+                        .ok_or(DecodeError::UnexpectedEnd)?
+                        & 0x7FF;
+
+                    (0..packets_count)
+                        .map(|_| decode_packet(reader))
+                        .collect::<Result<Vec<_>, _>>()?
                 };
 
+                // Comparison operators always compare exactly two sub-packets
+                if matches!(type_id, PACKET_GT | PACKET_LT | PACKET_EQ) && packets.len() != 2 {
+                    return Err(DecodeError::InvalidArity {
+                        type_id: type_id.0,
+                        count: packets.len(),
+                    });
+                }
+
                 let kind = match type_id {
                     PACKET_SUM => PacketKind::Sum(packets),
                     PACKET_PRODUCT => PacketKind::Product(packets),
@@ -298,16 +528,46 @@ mod bits {
                     PACKET_GT => PacketKind::Greater(packets),
                     PACKET_LT => PacketKind::Less(packets),
                     PACKET_EQ => PacketKind::Equal(packets),
-                    _ => return None,
+                    _ => unreachable!(),
                 };
 
-                Some(Packet { version, kind })
+                Ok(Packet {
+                    version,
+                    kind,
+                    bit_length: reader.offset() - start_offset,
+                })
             }
         }
     }
 }
 
-struct Day16;
+/// How many packets of each type id `root` holds, as printed by `--explain`
+fn type_summary(root: &Packet) -> String {
+    let mut histogram = root.type_histogram().into_iter().collect::<Vec<_>>();
+    histogram.sort_unstable();
+
+    let counts = histogram
+        .iter()
+        .map(|(type_id, count)| format!("{}={}", type_id, count))
+        .collect::<Vec<_>>();
+
+    format!("Packets by type: {}", counts.join(" "))
+}
+
+/// The literal values of `root`, depth first, as printed by `--explain`
+fn literals_summary(root: &Packet) -> String {
+    let literals = root
+        .literals()
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>();
+
+    format!("Literals: {}", literals.join(", "))
+}
+
+struct Day16 {
+    explain: bool,
+}
 
 impl Solver for Day16 {
     fn name(&self) -> &'static str {
@@ -321,22 +581,13 @@ impl Solver for Day16 {
             .ok_or(SolverError::Generic("Empty packets".into()))?;
 
         let bytes = hex::decode(&packets).map_err(|e| SolverError::Generic(e.into()))?;
-        let packets = bits::decode(bytes.as_slice());
-
-        let mut to_traverse = packets.iter().collect::<VecDeque<_>>();
-        let mut versions = Vec::new();
+        let packets = bits::decode(bytes.as_slice()).map_err(|e| SolverError::Generic(e.into()))?;
 
-        while let Some(packet) = to_traverse.pop_front() {
-            versions.push(packet.version() as u32);
-
-            if let Some(sub_packets) = packet.sub_packets() {
-                for packet in sub_packets {
-                    to_traverse.push_back(packet)
-                }
-            }
-        }
-
-        let sum: u32 = versions.iter().sum();
+        let sum: u32 = packets
+            .iter()
+            .flat_map(Packet::iter)
+            .map(|p| p.version() as u32)
+            .sum();
         Ok(sum.to_string())
     }
 
@@ -348,11 +599,17 @@ impl Solver for Day16 {
 
         let bytes = hex::decode(&packets).map_err(|e| SolverError::Generic(e.into()))?;
 
-        let packets = bits::decode(bytes.as_slice());
+        let packets = bits::decode(bytes.as_slice()).map_err(|e| SolverError::Generic(e.into()))?;
         let root = packets.first().ok_or(SolverError::Generic(
             "Failed to retrieve root packet".into(),
         ))?;
 
+        if self.explain {
+            print!("{}", root.pretty(0));
+            println!("{}", type_summary(root));
+            println!("{}", literals_summary(root));
+        }
+
         Ok(root.eval().to_string())
     }
 
@@ -363,15 +620,20 @@ impl Solver for Day16 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.explain = options.explain;
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day16)
+    Box::new(Day16 { explain: false })
 }
 
 #[cfg(test)]
 mod test {
     use super::bits::*;
+    use std::collections::HashMap;
 
     #[test]
     fn should_decode_varint() {
@@ -380,4 +642,201 @@ mod test {
 
         assert_eq!(Varint::decode(&mut reader), Some(Varint(2021, 3)));
     }
+
+    #[test]
+    fn should_peek_without_advancing() {
+        let bits = &[0b10111111, 0b10001010];
+        let mut reader = BitReader::new(bits, 3);
+
+        let peeked = reader.peek::<u8>(7);
+        assert_eq!(reader.offset(), 3);
+
+        assert_eq!(reader.consume::<u8>(7), peeked);
+        assert_eq!(peeked, Some(0b1111110));
+        assert_eq!(reader.offset(), 10);
+    }
+
+    #[test]
+    fn should_only_check_bits_left_for_zeros() {
+        assert!(BitReader::new(&[0b11100000, 0, 0], 3).only_zeros_left());
+        assert!(!BitReader::new(&[0b11100000, 0, 1], 3).only_zeros_left());
+        assert!(!BitReader::new(&[0b11110000, 0, 0], 3).only_zeros_left());
+        assert!(BitReader::new(&[0xFF], 8).only_zeros_left());
+    }
+
+    #[test]
+    fn should_reject_transmission_shorter_than_a_packet() {
+        assert!(matches!(
+            decode(&[0xD2]),
+            Err(DecodeError::TooShort { bits: 8 })
+        ));
+        assert!(matches!(
+            decode(&[]),
+            Err(DecodeError::TooShort { bits: 0 })
+        ));
+    }
+
+    #[test]
+    fn should_ignore_trailing_padding() {
+        // A literal 2021 followed by its own padding and a few more zero nibbles
+        let bytes = super::hex::decode("D2FE2800000000").unwrap();
+        let packets = decode(&bytes).unwrap();
+
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].eval(), 2021);
+
+        let reader = BitReader::new(&bytes, 21);
+        assert!(reader.only_zeros_left());
+        assert!(!BitReader::new(&bytes, 20).only_zeros_left());
+    }
+
+    #[test]
+    fn should_decode_packets_back_to_back() {
+        // Two literals 2021 of 21 bits each, the second one starting mid-byte
+        let bytes = super::hex::decode("D2FE2E97F140").unwrap();
+        let packets = decode(&bytes).unwrap();
+
+        assert_eq!(packets.len(), 2);
+        assert!(packets
+            .iter()
+            .all(|packet| packet.eval() == 2021 && packet.bit_length() == 21));
+    }
+
+    #[test]
+    fn should_reject_truncated_transmission() {
+        // An operator cut in the middle of its sub-packets length
+        let bytes = super::hex::decode("3800").unwrap();
+        assert!(matches!(decode(&bytes), Err(DecodeError::UnexpectedEnd)));
+
+        // An operator announcing two sub-packets but only carrying one, followed by padding
+        let bytes = super::hex::decode("06008408").unwrap();
+        assert!(matches!(decode(&bytes), Err(DecodeError::UnexpectedEnd)));
+    }
+
+    #[test]
+    fn should_pretty_print_packet_tree() {
+        // The sum of the literals 1 and 2
+        let bytes = super::hex::decode("C200B40A82").unwrap();
+        let packets = decode(&bytes).unwrap();
+
+        assert_eq!(
+            packets[0].pretty(0),
+            "v6 sum\n  v6 literal 1\n  v2 literal 2\n"
+        );
+    }
+
+    #[test]
+    fn should_consume_bytes() {
+        use std::borrow::Cow;
+
+        let bytes = &[0xD2, 0xFE, 0x28, 0x01];
+
+        let mut reader = BitReader::new(bytes, 8);
+        let consumed = reader.consume_bytes(2).unwrap();
+        assert!(matches!(consumed, Cow::Borrowed(&[0xFE, 0x28])));
+        assert_eq!(reader.offset(), 24);
+
+        let mut reader = BitReader::new(bytes, 4);
+        assert_eq!(reader.consume_bytes(2).unwrap().as_ref(), &[0x2F, 0xE2]);
+        assert_eq!(reader.offset(), 20);
+
+        assert!(reader.consume_bytes(2).is_none());
+        assert_eq!(reader.offset(), 20);
+        assert!(BitReader::new(bytes, 16).consume_bytes(3).is_none());
+    }
+
+    #[test]
+    fn should_measure_packet_bit_length() {
+        // A literal of value 2021, held in three groups
+        let bytes = super::hex::decode("D2FE28").unwrap();
+        let packets = decode(&bytes).unwrap();
+        assert_eq!(packets[0].bit_length(), 6 + 3 * 5);
+
+        // An operator with a 15 bits length of 27 bits of sub-packets
+        let bytes = super::hex::decode("38006F45291200").unwrap();
+        let packets = decode(&bytes).unwrap();
+        assert_eq!(packets[0].bit_length(), 6 + 1 + 15 + 27);
+    }
+
+    #[test]
+    fn should_decode_sum_type_id() {
+        let bytes = super::hex::decode("C200B40A82").unwrap();
+        let packets = decode(&bytes).unwrap();
+
+        let root = &packets[0];
+        assert_eq!(root.type_id(), 0);
+        assert!(!root.is_literal());
+
+        let operands = root.sub_packets().unwrap();
+        assert!(operands.iter().all(|p| p.is_literal() && p.type_id() == 4));
+    }
+
+    /// Pack a string of `0` and `1` into bytes, padding the last byte with zeros
+    fn from_bits(bits: &str) -> Vec<u8> {
+        bits.as_bytes()
+            .chunks(8)
+            .map(|chunk| {
+                let byte = chunk.iter().fold(0u8, |acc, b| (acc << 1) | (b - b'0'));
+                byte << (8 - chunk.len())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_reject_equal_packet_with_three_operands() {
+        // Equal operator (type 7) holding three literal packets of value 1
+        let literal = "000100".to_owned() + "00001";
+        let bits = "001111".to_owned() + "1" + "00000000011" + &literal.repeat(3);
+
+        assert_eq!(
+            decode(&from_bits(&bits)).unwrap_err(),
+            DecodeError::InvalidArity {
+                type_id: 7,
+                count: 3
+            }
+        );
+    }
+
+    #[test]
+    fn should_decode_equal_packet_with_two_operands() {
+        let literal = "000100".to_owned() + "00001";
+        let bits = "001111".to_owned() + "1" + "00000000010" + &literal.repeat(2);
+
+        let packets = decode(&from_bits(&bits)).unwrap();
+        assert_eq!(packets[0].type_id(), 7);
+        assert_eq!(packets[0].sub_packets().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn should_count_packets_by_type() {
+        // 1 + 3 == 2 * 2
+        let bytes = super::hex::decode("9C0141080250320F1802104A08").unwrap();
+        let packets = decode(&bytes).unwrap();
+
+        let histogram = packets[0].type_histogram();
+        let expected = HashMap::from([(7, 1), (0, 1), (1, 1), (4, 4)]);
+        assert_eq!(histogram, expected);
+
+        assert_eq!(
+            super::type_summary(&packets[0]),
+            "Packets by type: 0=1 1=1 4=4 7=1"
+        );
+    }
+
+    #[test]
+    fn should_collect_literals_in_order() {
+        // Product operator (type 1) holding the literals 2, 3 and 4
+        let literal = |value: &str| "000100".to_owned() + "0" + value;
+        let bits = "000001".to_owned()
+            + "1"
+            + "00000000011"
+            + &literal("0010")
+            + &literal("0011")
+            + &literal("0100");
+
+        let packets = decode(&from_bits(&bits)).unwrap();
+        assert_eq!(packets[0].literals(), vec![2, 3, 4]);
+        assert_eq!(super::literals_summary(&packets[0]), "Literals: 2, 3, 4");
+        assert_eq!(packets[0].eval(), 24);
+    }
 }
@@ -1,4 +1,4 @@
-use super::{Solver, SolverError, SolverResult};
+use super::{Options, Solver, SolverError, SolverResult};
 
 use std::error::Error;
 use std::fmt;
@@ -66,6 +66,16 @@ impl FromStr for Command {
     }
 }
 
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Forward(x) => write!(f, "forward {}", x),
+            Command::Down(x) => write!(f, "down {}", x),
+            Command::Up(x) => write!(f, "up {}", x),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Commands {
     /// The list of commands to execute
@@ -78,6 +88,26 @@ trait State {
 
     /// Get the `(horizontal position, depth)` tuple
     fn get(&self) -> (usize, usize);
+
+    /// Get the aim, states that do not aim stay at 0
+    fn aim(&self) -> usize {
+        0
+    }
+}
+
+/// Called with each command and the state after applying it
+type Trace<'a> = &'a mut dyn FnMut(&Command, &dyn State);
+
+/// Describe the effect of `cmd`, given the `state` after applying it
+fn describe(cmd: &Command, state: &dyn State) -> String {
+    let (horizontal, depth) = state.get();
+    format!(
+        "{} -> (horizontal={}, depth={}, aim={})",
+        cmd,
+        horizontal,
+        depth,
+        state.aim()
+    )
 }
 
 #[derive(Default, Debug)]
@@ -130,6 +160,10 @@ impl State for AimingState {
     fn get(&self) -> (usize, usize) {
         (self.horizontal, self.depth)
     }
+
+    fn aim(&self) -> usize {
+        self.aim
+    }
 }
 
 impl Commands {
@@ -143,21 +177,33 @@ impl Commands {
         Ok(Commands { commands })
     }
 
-    fn execute_on(&self, state: &mut dyn State) {
-        self.commands.iter().for_each(|c| state.mutate(c));
+    /// Apply every command to `state`, calling `trace` with the state after each of them
+    fn execute_on(&self, state: &mut dyn State, mut trace: Option<Trace>) {
+        for c in &self.commands {
+            state.mutate(c);
+
+            if let Some(trace) = trace.as_mut() {
+                trace(c, state);
+            }
+        }
     }
 }
 
-struct Day2;
+struct Day2 {
+    explain: bool,
+}
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day2)
+    Box::new(Day2 { explain: false })
 }
 
-fn solve<S: State + Default>(lines: Vec<String>) -> SolverResult {
+fn solve<S: State + Default>(lines: Vec<String>, explain: bool) -> SolverResult {
     let commands = Commands::new(lines)?;
     let mut state = S::default();
-    commands.execute_on(&mut state);
+
+    let mut narrate = |c: &Command, s: &dyn State| println!("{}", describe(c, s));
+    let trace: Option<Trace> = if explain { Some(&mut narrate) } else { None };
+    commands.execute_on(&mut state, trace);
 
     let (horizontal, depth) = state.get();
     Ok((horizontal * depth).to_string())
@@ -169,11 +215,11 @@ impl Solver for Day2 {
     }
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        solve::<BasicState>(lines)
+        solve::<BasicState>(lines, self.explain)
     }
 
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        solve::<AimingState>(lines)
+        solve::<AimingState>(lines, self.explain)
     }
 
     fn test_expected(&self, part: usize) -> &'static str {
@@ -183,4 +229,36 @@ impl Solver for Day2 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.explain = options.explain;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_narrate_sample_commands() {
+        let lines = ["forward 5", "down 5", "forward 8", "up 3"];
+        let commands = Commands::new(lines.iter().map(|l| l.to_string()).collect()).unwrap();
+
+        let mut narration = Vec::new();
+        let mut state = AimingState::default();
+        commands.execute_on(
+            &mut state,
+            Some(&mut |c: &Command, s: &dyn State| narration.push(describe(c, s))),
+        );
+
+        assert_eq!(
+            narration,
+            vec![
+                "forward 5 -> (horizontal=5, depth=0, aim=0)",
+                "down 5 -> (horizontal=5, depth=0, aim=5)",
+                "forward 8 -> (horizontal=13, depth=40, aim=5)",
+                "up 3 -> (horizontal=13, depth=40, aim=2)",
+            ]
+        );
+    }
 }
@@ -30,21 +30,32 @@ fn parse_reports(lines: Vec<String>) -> Result<Vec<ReportType>, SolverError> {
         .map_err(|e| SolverError::Generic(e.into()))
 }
 
-fn get_size(reports: &[ReportType]) -> Option<u32> {
-    // Find the total number of bits that we need to compute based on the maximum
-    // line size we got
-    reports
-        .iter()
-        .map(|x| ReportType::BITS - x.leading_zeros())
-        .max()
+fn get_size(lines: &[String]) -> Option<u32> {
+    // Find the total number of bits that we need to compute based on the maximum line size we
+    // got, the parsed values losing their leading zeros
+    lines.iter().map(|l| l.trim().len() as u32).max()
 }
 
 const MAX_REC: u32 = 1_00;
 
+/// Which reports to keep when there are as many ones as zeros for the current bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TieBreak {
+    PreferOnes,
+    PreferZeros,
+}
+
+/// Tie policy used to find the oxygen generator rating
+const OXYGEN_TIE_BREAK: TieBreak = TieBreak::PreferOnes;
+
+/// Tie policy used to find the CO2 scrubber rating
+const CO2_TIE_BREAK: TieBreak = TieBreak::PreferZeros;
+
 fn rating_rec<F: Fn(usize, usize) -> bool>(
     reports: &[ReportType],
     bit: u32,
     size: u32,
+    tie: TieBreak,
     f: F,
 ) -> Option<ReportType> {
     if reports.is_empty() || bit >= MAX_REC {
@@ -55,6 +66,11 @@ fn rating_rec<F: Fn(usize, usize) -> bool>(
         return Some(reports[0]);
     }
 
+    // Every bit has been looked at without telling the remaining reports apart
+    if bit >= size {
+        return None;
+    }
+
     let mask = (1 as ReportType) << (size - bit - 1);
 
     let mut zeros = Vec::new();
@@ -68,59 +84,100 @@ fn rating_rec<F: Fn(usize, usize) -> bool>(
         }
     }
 
-    if f(ones.len(), zeros.len()) {
-        rating_rec(ones.as_slice(), bit + 1, size, f)
+    let keep_ones = if ones.len() == zeros.len() {
+        tie == TieBreak::PreferOnes
     } else {
-        rating_rec(zeros.as_slice(), bit + 1, size, f)
-    }
-}
+        f(ones.len(), zeros.len())
+    };
 
-impl Solver for Day3 {
-    fn name(&self) -> &'static str {
-        "Binary Diagnostic"
+    if keep_ones {
+        rating_rec(ones.as_slice(), bit + 1, size, tie, f)
+    } else {
+        rating_rec(zeros.as_slice(), bit + 1, size, tie, f)
     }
+}
 
-    fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        let reports = parse_reports(lines)?;
-        let size = get_size(reports.as_slice()).unwrap();
-
-        let mut gamma_rate = ReportType::default();
-        let mut epsilon_rate = ReportType::default();
-
-        for bit in 0..size {
-            let mask = (1 as ReportType) << bit;
+/// The gamma rate times the epsilon rate, built from the most and least common bits
+fn power_consumption(reports: &[ReportType], size: u32) -> u32 {
+    let mut gamma_rate = ReportType::default();
+    let mut epsilon_rate = ReportType::default();
 
-            let mut zero_count = 0;
-            let mut one_count = 0;
+    for bit in 0..size {
+        let mask = (1 as ReportType) << bit;
 
-            for report in &reports {
-                if report & mask == mask {
-                    one_count += 1
-                } else {
-                    zero_count += 1
-                }
-            }
+        let mut zero_count = 0;
+        let mut one_count = 0;
 
-            if one_count > zero_count {
-                gamma_rate |= mask;
+        for report in reports {
+            if report & mask == mask {
+                one_count += 1
             } else {
-                epsilon_rate |= mask;
+                zero_count += 1
             }
         }
 
-        Ok((gamma_rate * epsilon_rate).to_string())
+        if one_count > zero_count {
+            gamma_rate |= mask;
+        } else {
+            epsilon_rate |= mask;
+        }
     }
 
-    fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
+    gamma_rate * epsilon_rate
+}
+
+/// The oxygen generator rating times the CO2 scrubber rating
+fn life_support_rating(reports: &[ReportType], size: u32) -> Result<u32, SolverError> {
+    let oxygen_generator = rating_rec(reports, 0, size, OXYGEN_TIE_BREAK, |ones, zeros| {
+        ones > zeros
+    })
+    .ok_or(SolverError::Generic(Error::RecursionLimit(MAX_REC).into()))?;
+    let co2_scrubber = rating_rec(reports, 0, size, CO2_TIE_BREAK, |ones, zeros| ones < zeros)
+        .ok_or(SolverError::Generic(Error::RecursionLimit(MAX_REC).into()))?;
+
+    Ok(oxygen_generator * co2_scrubber)
+}
+
+/// The diagnostic report of the submarine, parsed once for both ratings
+struct Report {
+    reports: Vec<ReportType>,
+
+    /// The width of the reports, in bits
+    size: u32,
+}
+
+impl Report {
+    fn parse(lines: Vec<String>) -> Result<Self, SolverError> {
+        let size = get_size(&lines).ok_or(SolverError::Generic("No diagnostic report".into()))?;
         let reports = parse_reports(lines)?;
-        let size = get_size(reports.as_slice()).unwrap();
 
-        let oxygen_generator = rating_rec(reports.as_slice(), 0, size, |ones, zeros| ones >= zeros)
-            .ok_or(SolverError::Generic(Error::RecursionLimit(MAX_REC).into()))?;
-        let co2_scrubber = rating_rec(reports.as_slice(), 0, size, |ones, zeros| zeros > ones)
-            .ok_or(SolverError::Generic(Error::RecursionLimit(MAX_REC).into()))?;
+        Ok(Self { reports, size })
+    }
+}
+
+/// The power consumption and the life support rating of the submarine, both read from the same
+/// diagnostic `report`. Only the life support rating can fail, which leaves the power consumption
+/// usable on its own
+fn diagnostics(report: &Report) -> (u32, Result<u32, SolverError>) {
+    (
+        power_consumption(&report.reports, report.size),
+        life_support_rating(&report.reports, report.size),
+    )
+}
+
+impl Solver for Day3 {
+    fn name(&self) -> &'static str {
+        "Binary Diagnostic"
+    }
+
+    fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
+        let (power_consumption, _) = diagnostics(&Report::parse(lines)?);
+        Ok(power_consumption.to_string())
+    }
 
-        Ok((oxygen_generator * co2_scrubber).to_string())
+    fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
+        let (_, life_support_rating) = diagnostics(&Report::parse(lines)?);
+        Ok(life_support_rating?.to_string())
     }
 
     fn test_expected(&self, part: usize) -> &'static str {
@@ -135,3 +192,73 @@ impl Solver for Day3 {
 pub(super) fn new() -> Box<dyn Solver> {
     Box::new(Day3)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    fn sample_lines() -> Vec<String> {
+        lines(&[
+            "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
+            "11001", "00010", "01010",
+        ])
+    }
+
+    fn sample() -> Vec<ReportType> {
+        parse_reports(sample_lines()).unwrap()
+    }
+
+    #[test]
+    fn should_find_ratings_with_default_tie_break() {
+        let reports = sample();
+
+        let oxygen = rating_rec(&reports, 0, 5, OXYGEN_TIE_BREAK, |o, z| o > z);
+        let co2 = rating_rec(&reports, 0, 5, CO2_TIE_BREAK, |o, z| o < z);
+
+        assert_eq!(oxygen, Some(23));
+        assert_eq!(co2, Some(10));
+    }
+
+    #[test]
+    fn should_find_ratings_with_opposite_tie_break() {
+        let reports = sample();
+
+        let oxygen = rating_rec(&reports, 0, 5, TieBreak::PreferZeros, |o, z| o > z);
+        let co2 = rating_rec(&reports, 0, 5, TieBreak::PreferOnes, |o, z| o < z);
+
+        assert_eq!(oxygen, Some(22));
+        assert_eq!(co2, Some(15));
+    }
+
+    #[test]
+    fn should_compute_sample_diagnostics() {
+        let (power_consumption, life_support_rating) =
+            diagnostics(&Report::parse(sample_lines()).unwrap());
+
+        assert_eq!(power_consumption, 198);
+        assert_eq!(life_support_rating.unwrap(), 230);
+        assert!(Report::parse(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn should_keep_power_consumption_without_life_support_rating() {
+        // Both reports are the same, so no bit ever tells them apart
+        let (power_consumption, life_support_rating) =
+            diagnostics(&Report::parse(lines(&["101", "101"])).unwrap());
+
+        assert_eq!(power_consumption, 0b101 * 0b010);
+        assert!(life_support_rating.is_err());
+    }
+
+    #[test]
+    fn should_keep_leading_zeros_in_report_width() {
+        let lines = lines(&["00100", "00010", "00110"]);
+
+        assert_eq!(get_size(&lines), Some(5));
+        assert_eq!(power_consumption(&parse_reports(lines).unwrap(), 5), 6 * 25);
+    }
+}
@@ -1,6 +1,8 @@
-use super::{Solver, SolverError, SolverResult};
+use super::{Options, Solver, SolverError, SolverResult};
 
-struct Day4;
+struct Day4 {
+    diagonals: bool,
+}
 
 mod bingo {
     use std::str::FromStr;
@@ -54,7 +56,14 @@ mod bingo {
 
     impl fmt::Display for ParseBoardError {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "{:?}", self)
+            match self {
+                ParseBoardError::InvalidCell(e) => write!(f, "invalid cell: {}", e),
+                ParseBoardError::InvalidMatrix(rows, columns) => write!(
+                    f,
+                    "a board of {} rows has a row of {} columns",
+                    rows, columns
+                ),
+            }
         }
     }
 
@@ -62,6 +71,7 @@ mod bingo {
 
     pub(super) trait State {}
 
+    #[allow(dead_code)]
     pub(super) struct Invalid {}
 
     #[derive(Debug)]
@@ -184,7 +194,9 @@ mod bingo {
     }
 
     impl Board<Ready> {
-        pub(super) fn draw(mut self, n: u32) -> Drawn {
+        /// Mark `n` and check whether the board won with a full row or column, or with one of the
+        /// two main diagonals of a square board if `diagonals` is set
+        pub(super) fn draw(mut self, n: u32, diagonals: bool) -> Drawn {
             let cell = self.state.cells.iter_mut().find(|c| c.value() == n);
             if let Some(cell) = cell {
                 cell.mark();
@@ -214,15 +226,15 @@ mod bingo {
                 }
             }
 
+            // Still no winner, check the diagonals if they count
+            if !won && diagonals && rows == columns {
+                won = (0..rows).all(|i| self.cell(i, i).is_marked())
+                    || (0..rows).all(|i| self.cell(i, columns - 1 - i).is_marked());
+            }
+
             // We won, let's compute our score
             if won {
-                let score: u32 = self
-                    .state
-                    .cells
-                    .iter()
-                    .filter(|c| !c.is_marked())
-                    .map(|c| c.value())
-                    .sum();
+                let score = self.unmarked_sum();
                 Drawn::Won(Board::<Win> {
                     state: Box::new(Win { score }),
                 })
@@ -231,10 +243,24 @@ mod bingo {
             }
         }
 
+        /// The sum of the cells that have not been marked yet
+        pub(super) fn unmarked_sum(&self) -> u32 {
+            self.state
+                .cells
+                .iter()
+                .filter(|c| !c.is_marked())
+                .map(|c| c.value())
+                .sum()
+        }
+
+        fn cell(&self, row: usize, column: usize) -> &Cell {
+            &self.state.cells[row * self.state.columns + column]
+        }
+
         fn iter_row<'a>(&'a self, row: usize) -> RowIterator<'a> {
             RowIterator {
-                cells: &self.state.cells.as_slice(),
-                row: row,
+                cells: self.state.cells.as_slice(),
+                row,
                 rows: self.state.rows,
                 current: 0,
             }
@@ -242,8 +268,8 @@ mod bingo {
 
         fn iter_column<'a>(&'a self, column: usize) -> ColumnIterator<'a> {
             ColumnIterator {
-                cells: &self.state.cells.as_slice(),
-                column: column,
+                cells: self.state.cells.as_slice(),
+                column,
                 columns: self.state.columns,
                 current: 0,
             }
@@ -258,7 +284,7 @@ mod bingo {
 }
 
 // Play and return the scores of winning boards by order
-fn play(lines: Vec<String>) -> Result<Vec<u32>, SolverError> {
+fn play(lines: Vec<String>, diagonals: bool) -> Result<Vec<u32>, SolverError> {
     let game = lines[0]
         .split(',')
         .map(|x| x.parse::<u32>())
@@ -283,7 +309,7 @@ fn play(lines: Vec<String>) -> Result<Vec<u32>, SolverError> {
         let mut new_boards = Vec::new();
 
         for board in boards.into_iter() {
-            match board.draw(g) {
+            match board.draw(g, diagonals) {
                 bingo::Drawn::Again(b) => new_boards.push(b),
                 bingo::Drawn::Won(b) => scores.push(b.score() * g),
             };
@@ -301,9 +327,9 @@ impl Solver for Day4 {
     }
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        let scores = play(lines)?;
+        let scores = play(lines, self.diagonals)?;
         scores
-            .get(0)
+            .first()
             .ok_or(SolverError::Generic(
                 "Could not determine a winner board".into(),
             ))
@@ -311,7 +337,7 @@ impl Solver for Day4 {
     }
 
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        let scores = play(lines)?;
+        let scores = play(lines, self.diagonals)?;
         let len = scores.len();
         let last = if len > 0 { len - 1 } else { 0 };
         scores
@@ -329,8 +355,58 @@ impl Solver for Day4 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.diagonals = options.diagonals;
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day4)
+    Box::new(Day4 { diagonals: false })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BOARD: &str = "22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19";
+
+    #[test]
+    fn should_sum_unmarked_cells_after_draws() {
+        let mut board = bingo::Board::parse(BOARD.lines().map(String::from).collect()).unwrap();
+        assert_eq!(board.unmarked_sum(), 300);
+
+        for n in [7, 4, 9, 5, 11] {
+            board = match board.draw(n, false) {
+                bingo::Drawn::Again(board) => board,
+                bingo::Drawn::Won(_) => panic!("Board should not have won yet"),
+            };
+        }
+
+        assert_eq!(board.unmarked_sum(), 264);
+    }
+
+    #[test]
+    fn should_win_on_diagonal_only_when_enabled() {
+        let draws = [22, 2, 14, 18, 19];
+        let play = |diagonals| {
+            let mut board = bingo::Board::parse(BOARD.lines().map(String::from).collect()).unwrap();
+
+            for n in draws {
+                board = match board.draw(n, diagonals) {
+                    bingo::Drawn::Again(board) => board,
+                    bingo::Drawn::Won(board) => return Some(board.score()),
+                };
+            }
+
+            None
+        };
+
+        assert_eq!(play(false), None);
+        assert_eq!(play(true), Some(300 - draws.iter().sum::<u32>()));
+    }
 }
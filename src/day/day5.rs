@@ -1,4 +1,5 @@
-use super::{Solver, SolverError, SolverResult};
+use super::grid::BoundingBox;
+use super::{Options, Solver, SolverError, SolverResult};
 
 use regex::Regex;
 use std::fmt;
@@ -18,10 +19,64 @@ struct Line {
     end: Point,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Orientation {
+    Horizontal,
+    Vertical,
+    Diagonal,
+}
+
+impl Orientation {
+    const ALL: [Orientation; 3] = [
+        Orientation::Horizontal,
+        Orientation::Vertical,
+        Orientation::Diagonal,
+    ];
+
+    fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl Line {
+    fn orientation(&self) -> Orientation {
+        if self.start.x == self.end.x {
+            Orientation::Vertical
+        } else if self.start.y == self.end.y {
+            Orientation::Horizontal
+        } else {
+            Orientation::Diagonal
+        }
+    }
+}
+
+/// How many overlapping points each orientation of line contributed to. Each point is counted
+/// once, points crossed by lines of different orientations being counted as mixed
+#[derive(Debug, Default, Eq, PartialEq)]
+struct Breakdown {
+    horizontal: usize,
+    vertical: usize,
+    diagonal: usize,
+    mixed: usize,
+}
+
+impl fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "horizontal={} vertical={} diagonal={} mixed={}",
+            self.horizontal, self.vertical, self.diagonal, self.mixed
+        )
+    }
+}
+
 #[derive(Debug)]
 struct Diagram {
     points: Vec<usize>,
 
+    /// The orientations of the lines covering each point, as a mask of `Orientation::mask`
+    orientations: Vec<u8>,
+
     rows: usize,
 
     columns: usize,
@@ -31,7 +86,7 @@ impl fmt::Display for Diagram {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         for i in 0..self.rows {
             for j in 0..self.columns {
-                let value = self.value(j, i);
+                let value = self.overlap_at(j, i);
                 if value == 0 {
                     formatter.write_char('.')?;
                 } else {
@@ -49,12 +104,15 @@ impl Diagram {
     fn new(rows: usize, columns: usize) -> Diagram {
         Diagram {
             points: vec![0usize; rows * columns],
+            orientations: vec![0u8; rows * columns],
             rows,
             columns,
         }
     }
 
     fn apply(&mut self, line: &Line, diag: bool) {
+        let orientation = line.orientation();
+
         let x1 = line.start.x as usize;
         let y1 = line.start.y as usize;
 
@@ -62,20 +120,20 @@ impl Diagram {
         let y2 = line.end.y as usize;
 
         if x1 == x2 {
-            let ys = (y1 as i64 - y2 as i64).abs() as usize;
+            let ys = (y1 as i64 - y2 as i64).unsigned_abs() as usize;
 
             let y1 = if y1 > y2 { y2 } else { y1 };
 
             for y in 0..ys + 1 {
-                self.incr(x1, y1 + y);
+                self.incr(x1, y1 + y, orientation);
             }
         } else if y1 == y2 {
-            let xs = (x1 as i64 - x2 as i64).abs() as usize;
+            let xs = (x1 as i64 - x2 as i64).unsigned_abs() as usize;
 
             let x1 = if x1 > x2 { x2 } else { x1 };
 
             for x in 0..xs + 1 {
-                self.incr(x1 + x, y1);
+                self.incr(x1 + x, y1, orientation);
             }
         } else if diag {
             let mut cur = line.start;
@@ -96,7 +154,7 @@ impl Diagram {
                     x: new_x as u64,
                     y: new_y as u64,
                 };
-                self.incr(new_x, new_y);
+                self.incr(new_x, new_y, orientation);
             }
         }
     }
@@ -105,9 +163,46 @@ impl Diagram {
         self.points[self.index(x, y)]
     }
 
-    fn incr(&mut self, x: usize, y: usize) {
+    fn incr(&mut self, x: usize, y: usize, orientation: Orientation) {
         let index = self.index(x, y);
         self.points[index] += 1;
+        self.orientations[index] |= orientation.mask();
+    }
+
+    fn overlaps(&self) -> usize {
+        self.points.iter().filter(|&x| *x >= 2).count()
+    }
+
+    fn breakdown(&self) -> Breakdown {
+        let mut breakdown = Breakdown::default();
+
+        for (count, mask) in self.points.iter().zip(&self.orientations) {
+            if *count < 2 {
+                continue;
+            }
+
+            let mut orientations = Orientation::ALL
+                .into_iter()
+                .filter(|orientation| mask & orientation.mask() != 0);
+
+            match (orientations.next(), orientations.next()) {
+                (Some(Orientation::Horizontal), None) => breakdown.horizontal += 1,
+                (Some(Orientation::Vertical), None) => breakdown.vertical += 1,
+                (Some(Orientation::Diagonal), None) => breakdown.diagonal += 1,
+                _ => breakdown.mixed += 1,
+            }
+        }
+
+        breakdown
+    }
+
+    /// Returns how many lines cover the point `(x, y)`, or 0 if the point lies outside the diagram
+    fn overlap_at(&self, x: usize, y: usize) -> usize {
+        if x >= self.columns || y >= self.rows {
+            return 0;
+        }
+
+        self.value(x, y)
     }
 
     fn index(&self, x: usize, y: usize) -> usize {
@@ -115,8 +210,12 @@ impl Diagram {
     }
 }
 
+const LINE_PATTERN: &str = r"(?P<x1>\d+),(?P<y1>\d+).*?->.*?(?P<x2>\d+),(?P<y2>\d+)";
+
 struct Day5 {
     re: Regex,
+
+    breakdown: bool,
 }
 
 impl Day5 {
@@ -161,34 +260,29 @@ impl Day5 {
     }
 }
 
-fn solve(lines: Vec<Line>, diag: bool) -> SolverResult {
-    let mut max_x = 0;
-    let mut max_y = 0;
-
-    for line in &lines {
-        if line.start.x > max_x {
-            max_x = line.start.x
-        }
-
-        if line.end.x > max_x {
-            max_x = line.end.x
-        }
+fn draw(lines: &[Line], diag: bool) -> Diagram {
+    let mut bounds = BoundingBox::default();
 
-        if line.start.y > max_y {
-            max_y = line.start.y
-        }
-
-        if line.end.y > max_y {
-            max_y = line.end.y
-        }
+    for line in lines {
+        bounds.extend((line.start.x as usize, line.start.y as usize));
+        bounds.extend((line.end.x as usize, line.end.y as usize));
     }
 
-    let mut diagram = Diagram::new(max_x as usize + 1, max_y as usize + 1);
+    let (width, height) = bounds.dimensions();
+    let mut diagram = Diagram::new(height, width);
     lines.iter().for_each(|l| diagram.apply(l, diag));
 
-    let count = diagram.points.iter().filter(|&x| *x >= 2).count();
+    diagram
+}
+
+fn solve(lines: Vec<Line>, diag: bool, breakdown: bool) -> SolverResult {
+    let diagram = draw(&lines, diag);
+
+    if breakdown {
+        println!("{}", diagram.breakdown());
+    }
 
-    Ok(count.to_string())
+    Ok(diagram.overlaps().to_string())
 }
 
 impl Solver for Day5 {
@@ -197,11 +291,11 @@ impl Solver for Day5 {
     }
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        solve(self.parse_lines(lines)?, false)
+        solve(self.parse_lines(lines)?, false, self.breakdown)
     }
 
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        solve(self.parse_lines(lines)?, true)
+        solve(self.parse_lines(lines)?, true, self.breakdown)
     }
 
     fn test_expected(&self, part: usize) -> &'static str {
@@ -211,9 +305,118 @@ impl Solver for Day5 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.breakdown = options.breakdown;
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    let re = Regex::new(r"(?P<x1>\d+),(?P<y1>\d+).*?->.*?(?P<x2>\d+),(?P<y2>\d+)").unwrap();
-    Box::new(Day5 { re })
+    let re = Regex::new(LINE_PATTERN).unwrap();
+    Box::new(Day5 {
+        re,
+        breakdown: false,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+
+    #[test]
+    fn should_break_down_sample_overlaps() {
+        let day = Day5 {
+            re: Regex::new(LINE_PATTERN).unwrap(),
+            breakdown: false,
+        };
+        let lines = day.parse_lines(SAMPLE.lines().map(String::from).collect());
+        let diagram = draw(&lines.unwrap(), true);
+
+        assert_eq!(diagram.overlaps(), 12);
+        assert_eq!(
+            diagram.breakdown(),
+            Breakdown {
+                horizontal: 4,
+                vertical: 0,
+                diagonal: 2,
+                mixed: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn should_break_down_crossing_point_once() {
+        let day = Day5 {
+            re: Regex::new(LINE_PATTERN).unwrap(),
+            breakdown: false,
+        };
+        let lines = day.parse_lines(vec![
+            "0,2 -> 4,2".to_string(),
+            "2,0 -> 2,4".to_string(),
+            "0,0 -> 4,4".to_string(),
+        ]);
+        let diagram = draw(&lines.unwrap(), true);
+
+        assert_eq!(diagram.overlaps(), 1);
+        assert_eq!(
+            diagram.breakdown(),
+            Breakdown {
+                mixed: 1,
+                ..Breakdown::default()
+            }
+        );
+    }
+
+    #[test]
+    fn should_not_alias_points_of_non_square_diagram() {
+        let day = Day5 {
+            re: Regex::new(LINE_PATTERN).unwrap(),
+            breakdown: false,
+        };
+        let lines = day.parse_lines(vec!["0,1 -> 3,1".to_string(), "2,0 -> 2,0".to_string()]);
+        let diagram = draw(&lines.unwrap(), false);
+
+        assert_eq!((diagram.rows, diagram.columns), (2, 4));
+        assert_eq!(diagram.overlaps(), 0);
+    }
+
+    #[test]
+    fn should_query_overlap_at_point() {
+        let day = Day5 {
+            re: Regex::new(LINE_PATTERN).unwrap(),
+            breakdown: false,
+        };
+        let lines = day.parse_lines(SAMPLE.lines().map(String::from).collect());
+        let diagram = draw(&lines.unwrap(), true);
+
+        assert_eq!(diagram.overlap_at(4, 4), 3);
+        assert_eq!(diagram.overlap_at(0, 9), 2);
+        assert_eq!(diagram.overlap_at(1, 0), 0);
+        assert_eq!(diagram.overlap_at(100, 100), 0);
+    }
+
+    #[test]
+    fn should_query_overlap_at_point_of_non_square_diagram() {
+        let day = Day5 {
+            re: Regex::new(LINE_PATTERN).unwrap(),
+            breakdown: false,
+        };
+        let lines = day.parse_lines(vec!["0,1 -> 3,1".to_string(), "3,0 -> 3,1".to_string()]);
+        let diagram = draw(&lines.unwrap(), false);
+
+        assert_eq!(diagram.overlap_at(3, 1), 2);
+        assert_eq!(diagram.overlap_at(1, 3), 0);
+        assert_eq!(diagram.to_string(), "...1\n1112\n");
+    }
 }
@@ -1,224 +1,252 @@
-use super::{Solver, SolverError, SolverResult};
+use super::{Options, Progress, Solver, SolverError, SolverResult};
 
 use std::result::Result;
 use std::vec::Vec;
 
-struct Day6;
+struct Day6 {
+    verbose: bool,
 
-#[derive(Debug)]
-struct LanternFish {
-    timer: u64,
+    progress: bool,
+
+    /// Number of simulated days overriding the default of both parts
+    days: Option<usize>,
 }
 
-const FISHY: usize = 409664;
+/// Default number of simulated days of the first part
+const PART1_DAYS: usize = 80;
 
-#[derive(Debug)]
-struct BinaryLanternFish {
-    timers: [u8; FISHY],
+/// Default number of simulated days of the second part
+const PART2_DAYS: usize = 256;
 
-    len: usize,
-}
+/// Timer of a freshly spawned lanternfish, the last slot of a histogram
+const NEW_FISH_TIMER: usize = 8;
 
-#[derive(Debug)]
-struct Spawns {
-    timers: [u8; FISHY],
+/// Timer of a lanternfish that just spawned a new one
+const FISH_RESET_TIMER: usize = 6;
 
-    index: usize,
-}
-
-impl Spawns {
-    fn new() -> Spawns {
-        Spawns {
-            timers: [0u8; FISHY],
-            index: 0,
-        }
-    }
+/// Number of simulated days between two progress reports
+const PROGRESS_DAYS: usize = 16;
 
-    fn add(&mut self, timer: u8) -> Option<()> {
-        if self.is_full() {
-            return None;
-        }
+fn parse_timers(lines: &[String]) -> Result<Vec<u8>, SolverError> {
+    let timers = lines
+        .first()
+        .ok_or(SolverError::Generic("Missing lanternfish timers".into()))?
+        .split(',')
+        .map(|s| s.parse::<u8>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| SolverError::Generic(e.into()))?;
 
-        self.timers[self.index] = timer;
-        self.index += 1;
-        Some(())
+    // Timers index the histograms, and no fish waits longer than a newborn one
+    match timers.iter().max() {
+        Some(&timer) if usize::from(timer) > NEW_FISH_TIMER => Err(SolverError::Generic(
+            format!("Invalid lanternfish timer {}", timer).into(),
+        )),
+        _ => Ok(timers),
     }
+}
 
-    fn merge_with(&mut self, other: Spawns) -> Option<Spawns> {
-        if self.is_full() {
-            return None;
-        }
-
-        let other_idx = other.index;
-        let mut cur_idx = 0;
+/// The error of a lanternfish count that no longer fits in a `u64` after `days`
+fn overflow(days: usize) -> SolverError {
+    SolverError::Generic(format!("Too many lanternfish to count after {} days", days).into())
+}
 
-        let mut new_spawns = Spawns::new();
+/// The number of lanternfish per timer value
+type Histogram = [u64; NEW_FISH_TIMER + 1];
 
-        while cur_idx != other_idx {
-            let other = other.timers[cur_idx];
+fn initial_histogram(initial: &[u8]) -> Histogram {
+    let mut histogram = [0u64; NEW_FISH_TIMER + 1];
+    for &timer in initial {
+        histogram[timer as usize] += 1;
+    }
 
-            if self.is_full() {
-                new_spawns.add(other);
-            } else {
-                self.add(other);
-            }
+    histogram
+}
 
-            cur_idx += 1;
-        }
+/// Move `histogram` on to the given `day`. Fails as soon as a count no longer fits in a `u64`
+fn step(histogram: &mut Histogram, day: usize) -> Result<(), SolverError> {
+    // Fishes with a timer of 0 reset their own timer and spawn a new fish each, rotating moves
+    // them to the slot of new fishes
+    let spawning = histogram[0];
+    histogram.rotate_left(1);
+    histogram[FISH_RESET_TIMER] = histogram[FISH_RESET_TIMER]
+        .checked_add(spawning)
+        .ok_or_else(|| overflow(day))?;
+
+    Ok(())
+}
 
-        if new_spawns.is_empty() {
-            None
-        } else {
-            Some(new_spawns)
+/// The histogram after `days`, starting from the `initial` timers
+fn histogram_after(
+    initial: &[u8],
+    days: usize,
+    mut progress: Progress,
+) -> Result<Histogram, SolverError> {
+    let mut histogram = initial_histogram(initial);
+
+    for day in 1..=days {
+        step(&mut histogram, day)?;
+
+        if let Some(progress) = progress
+            .as_mut()
+            .filter(|_| day.is_multiple_of(PROGRESS_DAYS))
+        {
+            progress(day);
         }
     }
 
-    fn is_empty(&self) -> bool {
-        self.index == 0
-    }
+    Ok(histogram)
+}
 
-    fn is_full(&self) -> bool {
-        self.index == FISHY
-    }
+/// The histograms from the `initial` timers and after each of the `days`, to plot the age
+/// structure of the population. The first histogram is the initial one, so there are `days + 1`
+/// of them
+fn histograms(initial: &[u8], days: usize) -> Result<Vec<Histogram>, SolverError> {
+    let mut histogram = initial_histogram(initial);
+    let mut histograms = vec![histogram];
 
-    fn into_fish(self) -> BinaryLanternFish {
-        BinaryLanternFish {
-            timers: self.timers,
-            len: self.index,
-        }
+    for day in 1..=days {
+        step(&mut histogram, day)?;
+        histograms.push(histogram);
     }
+
+    Ok(histograms)
 }
 
-impl BinaryLanternFish {
-    fn count(&self) -> usize {
-        self.len
-    }
+/// The number of lanternfish after `days`, starting from the `initial` timers. Fails when the
+/// count no longer fits in a `u64`
+fn simulate(initial: &[u8], days: usize, progress: Progress) -> Result<u64, SolverError> {
+    histogram_after(initial, days, progress)?
+        .iter()
+        .try_fold(0u64, |total, &count| total.checked_add(count))
+        .ok_or_else(|| overflow(days))
+}
 
-    fn spawn(&mut self) -> Option<Spawns> {
-        let mut spawns = Spawns::new();
-        for timer in self.timers.iter_mut().take(self.len) {
-            if *timer > 0 {
-                *timer -= 1;
-            } else {
-                *timer = FISH_RESET_TIMER as u8;
-                spawns.add(NEW_FISH_TIMER as u8);
-            }
-        }
+fn solve(lines: Vec<String>, days: usize, verbose: bool, progress: Progress) -> SolverResult {
+    let initial = parse_timers(&lines)?;
 
-        if spawns.is_empty() {
-            None
-        } else {
-            Some(spawns)
+    if verbose {
+        for (day, histogram) in histograms(&initial, days)?.iter().enumerate() {
+            println!("Day {}: {:?}", day, histogram);
         }
     }
+
+    Ok(simulate(&initial, days, progress)?.to_string())
 }
 
-const NEW_FISH_TIMER: u64 = 8;
-const FISH_RESET_TIMER: u64 = 6;
+impl Solver for Day6 {
+    fn name(&self) -> &'static str {
+        "Lanternfish"
+    }
 
-impl LanternFish {
-    pub fn with_timer(timer: u64) -> LanternFish {
-        LanternFish { timer }
+    fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
+        solve(lines, self.days.unwrap_or(PART1_DAYS), self.verbose, None)
     }
 
-    pub fn spawn(&mut self) -> Option<LanternFish> {
-        if self.timer == 0 {
-            self.timer = FISH_RESET_TIMER;
-            Some(LanternFish::with_timer(NEW_FISH_TIMER))
-        } else {
-            self.timer -= 1;
-            None
-        }
+    fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
+        let days = self.days.unwrap_or(PART2_DAYS);
+        let progress = super::stderr_progress(self.progress);
+        let result = solve(lines, days, self.verbose, progress);
+        super::end_stderr_progress(self.progress);
+
+        result
     }
-}
 
-fn solve_v1(lines: Vec<String>, days: usize) -> SolverResult {
-    let mut fishes = lines[0]
-        .split(',')
-        .map(|s| s.parse::<u64>().map(|x| LanternFish::with_timer(x)))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| SolverError::Generic(e.into()))?;
+    fn test_expected(&self, part: usize) -> &'static str {
+        match part {
+            1 => "5934",
+            2 => "26984457539",
+            _ => unreachable!(),
+        }
+    }
 
-    (0..days).for_each(|_| {
-        let new_fishes: Vec<_> = fishes.iter_mut().filter_map(|f| f.spawn()).collect();
-        fishes.extend(new_fishes);
-    });
+    fn configure(&mut self, options: &Options) {
+        self.verbose = options.verbose;
+        self.progress = options.progress;
+        self.days = options.days;
+    }
+}
 
-    Ok(fishes.len().to_string())
+pub(super) fn new() -> Box<dyn Solver> {
+    Box::new(Day6 {
+        verbose: false,
+        progress: false,
+        days: None,
+    })
 }
 
-fn solve_v2(lines: Vec<String>, days: usize) -> SolverResult {
-    let values = lines[0]
-        .split(',')
-        .map(|s| s.parse::<u8>())
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| SolverError::Generic(e.into()))?;
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    let mut fishes = Vec::new();
+    const SAMPLE: &str = "3,4,3,1,2";
 
-    let mut cur_spawns = Spawns::new();
-    for value in values {
-        cur_spawns.add(value);
+    fn sample() -> Vec<String> {
+        vec![SAMPLE.to_string()]
     }
 
-    fishes.push(cur_spawns.into_fish());
+    #[test]
+    fn should_compute_sample_histogram_after_18_days() {
+        let initial = [3, 4, 3, 1, 2];
 
-    for day in 0..days {
-        println!("day {}", day);
-        let mut new_fishes = Vec::new();
+        assert_eq!(
+            histogram_after(&initial, 0, None).unwrap(),
+            [0, 1, 1, 2, 1, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            histogram_after(&initial, 18, None).unwrap(),
+            [3, 5, 3, 2, 2, 1, 5, 1, 4]
+        );
+    }
 
-        let mut cur_spawns = Spawns::new();
+    #[test]
+    fn should_keep_sample_histogram_of_every_day() {
+        let histograms = histograms(&[3, 4, 3, 1, 2], 18).unwrap();
 
-        for fish in &mut fishes {
-            if let Some(spawns) = fish.spawn() {
-                if cur_spawns.is_full() {
-                    new_fishes.push(cur_spawns.into_fish());
-                    cur_spawns = Spawns::new();
-                }
+        assert_eq!(histograms.len(), 19);
+        assert_eq!(histograms[0], [0, 1, 1, 2, 1, 0, 0, 0, 0]);
+        assert_eq!(histograms[18], [3, 5, 3, 2, 2, 1, 5, 1, 4]);
+        assert_eq!(histograms[18].iter().sum::<u64>(), 26);
+    }
 
-                if let Some(new_spawns) = cur_spawns.merge_with(spawns) {
-                    new_fishes.push(cur_spawns.into_fish());
-                    cur_spawns = new_spawns;
-                }
-            }
-        }
+    #[test]
+    fn should_simulate_any_number_of_days() {
+        let initial = [3, 4, 3, 1, 2];
 
-        if !cur_spawns.is_empty() {
-            fishes.push(cur_spawns.into_fish());
-        }
+        assert_eq!(simulate(&initial, 18, None).unwrap(), 26);
+        assert_eq!(simulate(&initial, 80, None).unwrap(), 5934);
+        assert_eq!(simulate(&initial, 256, None).unwrap(), 26984457539);
+    }
 
-        fishes.extend(new_fishes);
-        println!("Vec size is {}", fishes.len());
+    #[test]
+    fn should_fail_when_count_overflows() {
+        assert!(simulate(&[3, 4, 3, 1, 2], 1000, None).is_err());
+        assert!(solve(sample(), 1000, false, None).is_err());
     }
 
-    print!("\n");
+    #[test]
+    fn should_stop_at_overflow_for_large_day_counts() {
+        assert!(simulate(&[3, 4, 3, 1, 2], 100_000_000, None).is_err());
+        assert!(histograms(&[3, 4, 3, 1, 2], 100_000_000).is_err());
+        assert!(solve(sample(), usize::MAX, false, None).is_err());
+    }
 
-    let total = fishes.iter().map(|f| f.count()).sum::<usize>();
-    Ok(total.to_string())
-}
+    #[test]
+    fn should_reject_timers_longer_than_new_fish() {
+        let lines = |line: &str| vec![line.to_string()];
 
-impl Solver for Day6 {
-    fn name(&self) -> &'static str {
-        "Lanternfish"
+        assert_eq!(parse_timers(&lines("3,4,8")).unwrap(), vec![3, 4, 8]);
+        assert!(parse_timers(&lines("3,9,1")).is_err());
     }
 
-    fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
-        solve_v1(lines, 80)
-    }
+    #[test]
+    fn should_report_progress_periodically() {
+        let mut reports = Vec::new();
+        let progress: Progress = Some(Box::new(|day| reports.push(day)));
 
-    fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
-        solve_v2(lines, 256)
-    }
+        histogram_after(&[3, 4, 3, 1, 2], 256, progress).unwrap();
 
-    fn test_expected(&self, part: usize) -> &'static str {
-        match part {
-            1 => "5934",
-            2 => "26984457539",
-            _ => unreachable!(),
-        }
+        assert_eq!(reports.len(), 256 / PROGRESS_DAYS);
+        assert_eq!(reports.first(), Some(&PROGRESS_DAYS));
+        assert_eq!(reports.last(), Some(&256));
     }
 }
-
-pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day6)
-}
@@ -1,5 +1,17 @@
+use super::stats::{argmin, median};
 use super::{Solver, SolverError, SolverResult};
 
+/// The fuel spent by a crab moving `distance` steps, each step costing one more than the
+/// previous one: 1 + 2 + ... + distance
+fn fuel_cost(distance: u64) -> u64 {
+    distance * (distance + 1) / 2
+}
+
+/// The fuel spent by all crabs moving to `target`, each step costing one
+fn linear_fuel(positions: &[u64], target: u64) -> u64 {
+    positions.iter().map(|pos| pos.abs_diff(target)).sum()
+}
+
 struct Day7;
 
 impl Solver for Day7 {
@@ -10,19 +22,23 @@ impl Solver for Day7 {
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
         let mut positions = lines[0]
             .split(',')
-            .into_iter()
             .map(|x| x.parse::<u64>())
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| SolverError::Generic(e.into()))?;
 
-        positions.sort();
-        let median_idx = (positions.len() + 1) / 2;
-        let median = positions[median_idx] as i64;
-
-        let spent_fuel: u64 = positions
+        // The sum of distances is minimal at the median. `median` returns the upper of the two
+        // central values of an even number of crabs and leaves the lower ones before it, so try
+        // the lower central value as well
+        let upper =
+            median(&mut positions).ok_or(SolverError::Generic("No crab positions".into()))?;
+        let lower = positions[..positions.len() / 2]
             .iter()
-            .map(|x| (*x as i64 - median).abs() as u64)
-            .sum();
+            .copied()
+            .max()
+            .filter(|_| positions.len().is_multiple_of(2))
+            .unwrap_or(upper);
+
+        let spent_fuel = linear_fuel(&positions, lower).min(linear_fuel(&positions, upper));
 
         Ok(spent_fuel.to_string())
     }
@@ -30,46 +46,23 @@ impl Solver for Day7 {
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
         let positions = lines[0]
             .split(',')
-            .into_iter()
             .map(|x| x.parse::<u64>())
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| SolverError::Generic(e.into()))?;
 
-        let max_pos = *positions.iter().max().unwrap();
-        let mut spent_fuels = Vec::new();
-
-        // Let's compute the fuel we need to spend for each possible position and move
-        for pos in &positions {
-            let mut spent_fuel = Vec::new();
+        let min_pos = positions.iter().copied().min().unwrap_or(0) as usize;
+        let max_pos = positions.iter().copied().max().unwrap_or(0) as usize;
 
-            for i in 0..max_pos + 1 {
-                let mut fuel = 0u64;
-                let (src, target) = if i > *pos { (*pos, i) } else { (i, *pos) };
-
-                (src..target).into_iter().enumerate().for_each(|(idx, _)| {
-                    fuel += idx as u64 + 1;
-                });
-
-                spent_fuel.push(fuel);
-            }
-
-            spent_fuels.push(spent_fuel);
-        }
-
-        // We now compute the total fuel we need to spend for each possible move
-        let mut fuels = Vec::new();
-
-        for i in 0..max_pos + 1 {
-            let mut spent = 0u64;
-            for f in &spent_fuels {
-                spent += f.get(i as usize).unwrap();
-            }
-
-            fuels.push(spent);
-        }
+        // The answer is the position between the outermost crabs minimizing the total fuel
+        let (_, spent_fuel) = argmin(min_pos..max_pos + 1, |target| {
+            positions
+                .iter()
+                .map(|pos| fuel_cost(pos.abs_diff(target as u64)))
+                .sum::<u64>()
+        })
+        .filter(|_| !positions.is_empty())
+        .ok_or(SolverError::Generic("No crab positions".into()))?;
 
-        // And our answer is the minimum
-        let spent_fuel = fuels.iter().min().unwrap();
         Ok(spent_fuel.to_string())
     }
 
@@ -85,3 +78,67 @@ impl Solver for Day7 {
 pub(super) fn new() -> Box<dyn Solver> {
     Box::new(Day7)
 }
+
+#[cfg(test)]
+mod test {
+    use super::super::stats::pseudo_random;
+    use super::*;
+
+    /// The least fuel to align all crabs, moving them one step at a time
+    fn brute_force_fuel(positions: &[u64]) -> u64 {
+        let max_pos = *positions.iter().max().unwrap();
+
+        (0..=max_pos)
+            .map(|target| {
+                positions
+                    .iter()
+                    .map(|&pos| {
+                        let (mut fuel, mut step) = (0, 0);
+                        for _ in 0..pos.abs_diff(target) {
+                            step += 1;
+                            fuel += step;
+                        }
+                        fuel
+                    })
+                    .sum::<u64>()
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn should_align_crabs_on_median() {
+        let solve = |positions: &str| Day7.solve_part1(vec![positions.to_string()]).unwrap();
+
+        assert_eq!(solve("16,1,2,0,4,2,7,1,2,14"), "37");
+        assert_eq!(solve("5"), "0");
+        assert_eq!(solve("3,9"), "6");
+        assert_eq!(solve("1,2,10"), "9");
+    }
+
+    #[test]
+    fn should_align_sample_crabs_with_increasing_costs() {
+        let fuel = Day7.solve_part2(vec!["16,1,2,0,4,2,7,1,2,14".to_string()]);
+
+        assert_eq!(fuel.unwrap(), "168");
+    }
+
+    #[test]
+    fn should_match_brute_force_fuel_on_random_positions() {
+        for (seed, count) in [(1, 1), (7, 2), (42, 10), (1337, 50)] {
+            let positions = pseudo_random(seed, count, 100);
+            let line = positions
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let fuel = Day7.solve_part2(vec![line]).unwrap();
+            assert_eq!(fuel, brute_force_fuel(&positions).to_string());
+        }
+
+        assert_eq!(fuel_cost(0), 0);
+        assert_eq!(fuel_cost(1), 1);
+        assert_eq!(fuel_cost(11), 66);
+    }
+}
@@ -1,3 +1,4 @@
+use super::parse::tokens;
 use super::{Solver, SolverError, SolverResult};
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -12,13 +13,20 @@ enum Error {
     MissingOutput,
     InvalidSegment(char),
     InvalidWiring(String),
+
+    /// The patterns of an entry do not describe the ten digits
+    InconsistentPatterns,
 }
 
 impl std::error::Error for Error {}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Error::InvalidSegment(c) => write!(f, "invalid segment {:?}", c),
+            Error::InvalidWiring(wiring) => write!(f, "invalid wiring {:?}", wiring),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
@@ -74,18 +82,10 @@ struct Digit {
 }
 
 impl Digit {
-    fn is_unique(&self) -> bool {
-        if let Some(val) = self.value {
-            matches!(val, 1 | 4 | 7 | 8)
-        } else {
-            false
-        }
-    }
-
     fn common_segments(&self, other: &Digit) -> Vec<Segment> {
         let mut common = Vec::new();
         for segment in &self.wiring.segments {
-            if let Some(_) = &other.wiring.segments.iter().find(|&&s| *segment == s) {
+            if other.wiring.segments.contains(segment) {
                 common.push(*segment);
             }
         }
@@ -131,20 +131,18 @@ impl FromStr for Entry {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split('|').into_iter();
+        let mut split = s.split('|');
 
         let pattern = split.next().ok_or(Error::MissingInput)?;
         let output = split.next().ok_or(Error::MissingOutput)?;
 
-        let pattern = pattern
-            .split(' ')
-            .filter(|x| !x.is_empty())
+        let pattern = tokens(pattern, &[' ', '\t'])
+            .into_iter()
             .map(Digit::from_str)
             .collect::<Result<Vec<_>, _>>()?;
 
-        let output = output
-            .split(' ')
-            .filter(|x| !x.is_empty())
+        let output = tokens(output, &[' ', '\t'])
+            .into_iter()
             .map(Digit::from_str)
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -152,7 +150,8 @@ impl FromStr for Entry {
     }
 }
 
-fn solve_entry(entry: &Entry) -> u64 {
+/// Decode the digits of the output value of `entry`, most significant first
+fn decode_output(entry: &Entry) -> Result<Vec<u32>, Error> {
     let mut known_digits = {
         let known_digits = entry.pattern.iter().filter(|p| p.value.is_some());
 
@@ -191,10 +190,9 @@ fn solve_entry(entry: &Entry) -> u64 {
     };
 
     let mut solved = Vec::new();
-    loop {
-        if known_digits.len() == 10 {
-            break;
-        }
+    while known_digits.len() < 10 {
+        let known = known_digits.len();
+        let candidates = unsolved.values().map(Vec::len).sum::<usize>();
 
         for (digit, patterns) in &mut unsolved {
             if patterns.len() == 1 {
@@ -206,10 +204,9 @@ fn solve_entry(entry: &Entry) -> u64 {
             }
 
             let ps = patterns.iter().filter(|&p1| {
-                solved
+                !solved
                     .iter()
-                    .find(|&p2| p1.wiring.segments == p2.wiring.segments)
-                    .is_none()
+                    .any(|p2| p1.wiring.segments == p2.wiring.segments)
             });
 
             let mut possible = Vec::new();
@@ -248,13 +245,7 @@ fn solve_entry(entry: &Entry) -> u64 {
                             possible.push(pattern.clone());
                         }
                     }
-                } else if *digit == 5 {
-                    if let &[Some(one), Some(four), Some(seven)] = &common_segments {
-                        if one >= 1 && four >= 3 && seven >= 2 {
-                            possible.push(pattern.clone());
-                        }
-                    }
-                } else if *digit == 6 {
+                } else if *digit == 5 || *digit == 6 {
                     if let &[Some(one), Some(four), Some(seven)] = &common_segments {
                         if one >= 1 && four >= 3 && seven >= 2 {
                             possible.push(pattern.clone());
@@ -271,30 +262,52 @@ fn solve_entry(entry: &Entry) -> u64 {
 
             *patterns = possible;
         }
+
+        // Neither a digit nor a candidate was settled, another pass would not do any better
+        let candidates_left = unsolved.values().map(Vec::len).sum::<usize>();
+        if known_digits.len() == known && candidates_left == candidates {
+            return Err(Error::InconsistentPatterns);
+        }
     }
 
     let digits = known_digits.values().collect::<Vec<_>>();
-    let mut result = 0u64;
-    let mut mul = 1;
-    for digit in entry.output.iter() {
-        let value = match digit.value {
-            Some(value) => value,
+    entry
+        .output
+        .iter()
+        .map(|digit| match digit.value {
+            Some(value) => Ok(value),
             None => {
                 let len = digit.wiring.segments.len();
-                let d = digits.iter().find(|&&d| {
-                    let common = d.common_segments(digit);
-                    len == common.len() && len == d.wiring.segments.len()
-                });
-
-                d.unwrap().value.unwrap()
+                digits
+                    .iter()
+                    .find(|&&d| {
+                        let common = d.common_segments(digit);
+                        len == common.len() && len == d.wiring.segments.len()
+                    })
+                    .and_then(|d| d.value)
+                    .ok_or(Error::InconsistentPatterns)
             }
-        };
+        })
+        .collect()
+}
+
+fn solve_entry(entry: &Entry) -> Result<u64, Error> {
+    Ok(decode_output(entry)?
+        .into_iter()
+        .fold(0u64, |acc, digit| acc * 10 + digit as u64))
+}
 
-        result += (value as u64) * (1000 / mul);
-        mul *= 10;
+/// How many times each digit appears in the decoded output values of all `entries`
+fn digit_frequency(entries: &[Entry]) -> Result<[u64; 10], Error> {
+    let mut frequency = [0u64; 10];
+
+    for entry in entries {
+        for digit in decode_output(entry)? {
+            frequency[digit as usize] += 1;
+        }
     }
 
-    result
+    Ok(frequency)
 }
 
 impl Solver for Day8 {
@@ -309,10 +322,9 @@ impl Solver for Day8 {
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| SolverError::Generic(e.into()))?;
 
-        let unique_output_digits: usize = entries
-            .iter()
-            .map(|e| e.output.iter().filter(|d| d.is_unique()).count())
-            .sum();
+        // 1, 4, 7 and 8 are the only digits with a unique number of segments
+        let frequency = digit_frequency(&entries).map_err(|e| SolverError::Generic(e.into()))?;
+        let unique_output_digits: u64 = [1, 4, 7, 8].iter().map(|&d| frequency[d]).sum();
 
         Ok(unique_output_digits.to_string())
     }
@@ -324,7 +336,11 @@ impl Solver for Day8 {
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| SolverError::Generic(e.into()))?;
 
-        let sum: u64 = entries.iter().map(solve_entry).sum();
+        let sum = entries
+            .iter()
+            .map(solve_entry)
+            .sum::<Result<u64, _>>()
+            .map_err(|e| SolverError::Generic(e.into()))?;
         Ok(sum.to_string())
     }
 
@@ -340,3 +356,53 @@ impl Solver for Day8 {
 pub(super) fn new() -> Box<dyn Solver> {
     Box::new(Day8)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = "\
+be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
+
+    #[test]
+    fn should_count_sample_digit_frequency() {
+        let entries = SAMPLE
+            .lines()
+            .map(Entry::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            digit_frequency(&entries).unwrap(),
+            [0, 8, 1, 4, 6, 3, 2, 5, 7, 4]
+        );
+    }
+
+    #[test]
+    fn should_reject_inconsistent_patterns() {
+        let entry =
+            Entry::from_str("ab abc abcd abcdefg ab ab ab ab ab ab | ab abc abcd abcdefg").unwrap();
+        assert!(matches!(
+            decode_output(&entry),
+            Err(Error::InconsistentPatterns)
+        ));
+
+        // The five and six segment patterns all wire the same digit
+        let entry =
+            Entry::from_str("ab abc abcd abcdefg abcde abcde abcde abcdef abcdef abcdef | abcde")
+                .unwrap();
+        assert!(matches!(
+            decode_output(&entry),
+            Err(Error::InconsistentPatterns)
+        ));
+    }
+}
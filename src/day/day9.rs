@@ -1,4 +1,5 @@
-use super::{Solver, SolverError, SolverResult};
+use super::grid::{self, Grid};
+use super::{Options, Solver, SolverError, SolverResult};
 use std::collections::HashSet;
 
 #[derive(Debug)]
@@ -15,96 +16,134 @@ impl Heightmap {
         self.positions[x * self.columns + y]
     }
 
-    fn get_adj_index(&self, x: usize, y: usize) -> impl Iterator<Item = Option<(usize, usize)>> {
-        const DIRECTIONS: &'static [(i32, i32)] = &[(0, -1), (0, 1), (-1, 0), (1, 0)];
+    fn get_adj_index(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        grid::neighbors((x, y), (self.rows, self.columns), false)
+    }
+}
 
-        let rows = self.rows - 1;
-        let columns = self.columns - 1;
+fn parse_heightmap(lines: Vec<String>) -> Result<Heightmap, SolverError> {
+    let grid = Grid::from_lines(&lines, |c| c.to_digit(10))?;
 
-        DIRECTIONS.iter().map(move |d| {
-            let (d_x, d_y) = d;
+    Ok(Heightmap {
+        rows: grid.rows(),
+        columns: grid.columns(),
+        positions: grid.into_cells(),
+    })
+}
 
-            let (x, y) = {
-                let x = if *d_x < 0 {
-                    x.checked_sub(d_x.abs() as usize)
-                } else {
-                    Some(x + *d_x as usize)
-                };
+/// Flood fill the positions connected to `(x, y)` through heights lower than 9, whatever the
+/// slope between them
+fn walk_basin_rec(map: &Heightmap, x: usize, y: usize, walked: &mut HashSet<(usize, usize)>) {
+    let adj_indexes = map.get_adj_index(x, y);
+    for index in adj_indexes {
+        if map.position_at(index.0, index.1) < 9 && walked.insert(index) {
+            walk_basin_rec(map, index.0, index.1, walked);
+        }
+    }
+}
 
-                let y = if *d_y < 0 {
-                    y.checked_sub(d_y.abs() as usize)
-                } else {
-                    Some(y + *d_y as usize)
-                };
+/// The `(row, column)` positions of the basin flowing down to the low point at `(x, y)`, sorted
+fn walk_basin(map: &Heightmap, x: usize, y: usize) -> Vec<(usize, usize)> {
+    let mut walked = HashSet::from([(x, y)]);
+    walk_basin_rec(map, x, y, &mut walked);
 
-                (x, y)
-            };
+    let mut positions = walked.into_iter().collect::<Vec<_>>();
+    positions.sort_unstable();
+    positions
+}
 
-            match (x, y) {
-                (Some(x), Some(y)) => {
-                    if x > rows || y > columns {
-                        None
-                    } else {
-                        Some((x, y))
-                    }
-                }
-                _ => None,
-            }
-        })
-    }
+/// Every basin, one per low point, in the same order as `low_points`
+fn basins(map: &Heightmap) -> Vec<Vec<(usize, usize)>> {
+    low_points(map)
+        .into_iter()
+        .map(|(x, y)| walk_basin(map, x, y))
+        .collect()
 }
 
-fn parse_line(line: &str) -> Option<Vec<u32>> {
-    line.chars()
-        .map(|c| c.to_digit(10))
-        .collect::<Option<Vec<u32>>>()
+/// The `count` largest basins, largest first
+fn largest_basins(map: &Heightmap, count: usize) -> Vec<Vec<(usize, usize)>> {
+    let mut basins = basins(map);
+    basins.sort_by_key(|b| std::cmp::Reverse(b.len()));
+    basins.truncate(count);
+
+    basins
 }
 
-fn parse_heightmap(lines: Vec<String>) -> Result<Heightmap, SolverError> {
-    let mut positions = Vec::new();
-    let mut columns = 0usize;
-    for line in &lines {
-        let cols = parse_line(&line).ok_or(SolverError::Generic("Invalid line".into()))?;
-        columns = cols.len();
+/// Find the `(row, column)` positions that are lower than all of their adjacent positions
+fn low_points(map: &Heightmap) -> Vec<(usize, usize)> {
+    let mut points = Vec::new();
+
+    for i in 0..map.rows {
+        for j in 0..map.columns {
+            let current = map.position_at(i, j);
+            let is_low =
+                grid::neighbors_satisfy((i, j), (map.rows, map.columns), false, |(x, y)| {
+                    current < map.position_at(x, y)
+                });
 
-        positions.extend(cols);
+            if is_low {
+                points.push((i, j));
+            }
+        }
     }
 
-    Ok(Heightmap {
-        positions,
-        rows: lines.len(),
-        columns,
-    })
+    points
 }
 
-fn walk_basin_rec(
-    map: &Heightmap,
-    x: usize,
-    y: usize,
-    previous: u32,
-    walked: &mut HashSet<(usize, usize)>,
-) {
-    let adj_indexes = map.get_adj_index(x, y);
-    for adj_index in adj_indexes {
-        if let Some(index) = adj_index {
-            let value = map.position_at(index.0, index.1);
+/// Flood every basin, the areas delimited by heights of 9, and make sure each of them holds
+/// exactly one low point
+fn check_basins(map: &Heightmap) -> Result<(), String> {
+    let low_points = low_points(map).into_iter().collect::<HashSet<_>>();
+    let mut flooded = HashSet::new();
+    let mut errors = Vec::new();
+
+    for i in 0..map.rows {
+        for j in 0..map.columns {
+            if map.position_at(i, j) == 9 || !flooded.insert((i, j)) {
+                continue;
+            }
+
+            let mut lows = 0;
+            let mut pending = vec![(i, j)];
+
+            while let Some(position) = pending.pop() {
+                if low_points.contains(&position) {
+                    lows += 1;
+                }
 
-            if value > previous && value < 9 {
-                walked.insert(index);
-                walk_basin_rec(map, index.0, index.1, value, walked);
+                for (x, y) in map.get_adj_index(position.0, position.1) {
+                    if map.position_at(x, y) != 9 && flooded.insert((x, y)) {
+                        pending.push((x, y));
+                    }
+                }
+            }
+
+            if lows != 1 {
+                errors.push(format!("basin at ({}, {}) has {} low points", i, j, lows));
             }
         }
     }
-}
 
-fn walk_basin(map: &Heightmap, x: usize, y: usize, current: u32) -> usize {
-    let mut walked = HashSet::new();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join(", "))
+    }
+}
 
-    walk_basin_rec(map, x, y, current, &mut walked);
-    walked.len() + 1
+/// The risk level of every low point, in the same order as `low_points`
+fn risk_levels(map: &Heightmap) -> Vec<u32> {
+    low_points(map)
+        .into_iter()
+        .map(|(x, y)| map.position_at(x, y) + 1)
+        .collect()
 }
 
-struct Day9;
+struct Day9 {
+    check: bool,
+
+    show_basins: bool,
+}
 
 impl Solver for Day9 {
     fn name(&self) -> &'static str {
@@ -113,23 +152,7 @@ impl Solver for Day9 {
 
     fn solve_part1(&self, lines: Vec<String>) -> SolverResult {
         let heightmap = parse_heightmap(lines)?;
-
-        let mut res = 0u64;
-        for i in 0..heightmap.rows {
-            for j in 0..heightmap.columns {
-                let current = heightmap.position_at(i, j);
-                let adj_index = heightmap.get_adj_index(i, j);
-
-                let mut adj_values =
-                    adj_index.map(|idx| idx.map(|(x, y)| heightmap.position_at(x, y)));
-
-                let is_low = adj_values.all(|x| x.map(|v| current < v).unwrap_or(true));
-
-                if is_low {
-                    res += (current + 1) as u64;
-                }
-            }
-        }
+        let res: u64 = risk_levels(&heightmap).into_iter().map(u64::from).sum();
 
         Ok(res.to_string())
     }
@@ -137,27 +160,21 @@ impl Solver for Day9 {
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult {
         let heightmap = parse_heightmap(lines)?;
 
-        let mut basins = Vec::new();
-
-        for i in 0..heightmap.rows {
-            for j in 0..heightmap.columns {
-                let current = heightmap.position_at(i, j);
-                let adj_index = heightmap.get_adj_index(i, j);
-
-                let mut adj_values =
-                    adj_index.map(|idx| idx.map(|(x, y)| heightmap.position_at(x, y)));
+        if self.check {
+            if let Err(e) = check_basins(&heightmap) {
+                println!("Warning: {}", e);
+            }
+        }
 
-                let is_low = adj_values.all(|x| x.map(|v| current < v).unwrap_or(true));
+        let basins = largest_basins(&heightmap, 3);
 
-                if is_low {
-                    let len = walk_basin(&heightmap, i, j, current);
-                    basins.push(len);
-                }
+        if self.show_basins {
+            for basin in &basins {
+                println!("Basin of size {}: {:?}", basin.len(), basin);
             }
         }
 
-        basins.sort();
-        let res = basins.iter().rev().take(3).fold(1, |acc, x| acc * x);
+        let res = basins.iter().map(Vec::len).product::<usize>();
 
         Ok(res.to_string())
     }
@@ -169,8 +186,72 @@ impl Solver for Day9 {
             _ => unreachable!(),
         }
     }
+
+    fn configure(&mut self, options: &Options) {
+        self.check = options.check;
+        self.show_basins = options.show_basins;
+    }
 }
 
 pub(super) fn new() -> Box<dyn Solver> {
-    Box::new(Day9)
+    Box::new(Day9 {
+        check: false,
+        show_basins: false,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = "2199943210\n3987894921\n9856789892\n8767896789\n9899965678";
+
+    fn sample() -> Heightmap {
+        parse_heightmap(SAMPLE.lines().map(String::from).collect()).unwrap()
+    }
+
+    #[test]
+    fn should_find_sample_risk_levels() {
+        let heightmap = sample();
+
+        assert_eq!(low_points(&heightmap), vec![(0, 1), (0, 9), (2, 2), (4, 6)]);
+        assert_eq!(risk_levels(&heightmap), vec![2, 1, 6, 6]);
+    }
+
+    #[test]
+    fn should_find_three_largest_sample_basins() {
+        let basins = largest_basins(&sample(), 3);
+
+        assert_eq!(
+            basins.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![14, 9, 9]
+        );
+        assert_eq!(basins[0][0], (1, 2));
+
+        let answer = new().solve_part2(SAMPLE.lines().map(String::from).collect());
+        assert_eq!(answer.unwrap(), "1134");
+    }
+
+    #[test]
+    fn should_fill_basins_across_flat_and_falling_heights() {
+        // The walk from the low point crosses the plateau of 2s, then goes down to the 1
+        let lines = vec!["02219".to_string(), "99919".to_string()];
+        let heightmap = parse_heightmap(lines).unwrap();
+
+        assert_eq!(walk_basin(&heightmap, 0, 0).len(), 5);
+    }
+
+    #[test]
+    fn should_check_one_low_point_per_basin() {
+        assert_eq!(check_basins(&sample()), Ok(()));
+
+        // Both 0 are low points of the same basin, the 1s on the right have none
+        let lines = vec!["01209119".to_string()];
+        let heightmap = parse_heightmap(lines).unwrap();
+
+        assert_eq!(
+            check_basins(&heightmap),
+            Err("basin at (0, 0) has 2 low points, basin at (0, 5) has 0 low points".to_string())
+        );
+    }
 }
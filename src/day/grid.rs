@@ -0,0 +1,426 @@
+use super::SolverError;
+
+use std::fmt;
+
+/// Move from `position` by `delta`, returning `None` if the result falls outside of a grid of
+/// `(rows, columns)` dimensions
+pub(super) fn checked_neighbor(
+    position: (usize, usize),
+    delta: (i32, i32),
+    dimensions: (usize, usize),
+) -> Option<(usize, usize)> {
+    let x = position.0.checked_add_signed(delta.0 as isize)?;
+    let y = position.1.checked_add_signed(delta.1 as isize)?;
+
+    if x < dimensions.0 && y < dimensions.1 {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+/// Moves to the left, right, top and bottom neighbors
+const ORTHOGONAL: &[(i32, i32)] = &[(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+/// Moves to the orthogonal neighbors, followed by the diagonal ones
+const ALL_DIRECTIONS: &[(i32, i32)] = &[
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, -1),
+    (-1, 1),
+];
+
+/// The neighbors of `position` that fall inside a grid of `(rows, columns)` dimensions,
+/// including the diagonal ones if `diagonals` is set
+pub(super) fn neighbors(
+    position: (usize, usize),
+    dimensions: (usize, usize),
+    diagonals: bool,
+) -> impl Iterator<Item = (usize, usize)> {
+    let directions = if diagonals {
+        ALL_DIRECTIONS
+    } else {
+        ORTHOGONAL
+    };
+
+    directions
+        .iter()
+        .filter_map(move |&d| checked_neighbor(position, d, dimensions))
+}
+
+/// Whether every neighbor of `position` satisfies `pred`, see `neighbors`
+pub(super) fn neighbors_satisfy<F>(
+    position: (usize, usize),
+    dimensions: (usize, usize),
+    diagonals: bool,
+    pred: F,
+) -> bool
+where
+    F: Fn((usize, usize)) -> bool,
+{
+    neighbors(position, dimensions, diagonals).all(pred)
+}
+
+/// How many neighbors of `position` satisfy `pred`, see `neighbors`
+#[allow(dead_code)]
+pub(super) fn count_neighbors<F>(
+    position: (usize, usize),
+    dimensions: (usize, usize),
+    diagonals: bool,
+    pred: F,
+) -> usize
+where
+    F: Fn((usize, usize)) -> bool,
+{
+    neighbors(position, dimensions, diagonals)
+        .filter(|&p| pred(p))
+        .count()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum GridParseError {
+    /// A row does not have the same width as the first one, rows start at 1
+    Ragged {
+        row: usize,
+        got: usize,
+        expected: usize,
+    },
+
+    /// A character could not be converted to a cell, rows and columns start at 1
+    BadChar { row: usize, col: usize, ch: char },
+
+    /// The grid does not have any cell
+    Empty,
+}
+
+impl fmt::Display for GridParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridParseError::Ragged { row, got, expected } => {
+                write!(f, "row {} has {} columns, expected {}", row, got, expected)
+            }
+            GridParseError::BadChar { row, col, ch } => write!(
+                f,
+                "row {} has an invalid cell {:?} at column {}",
+                row, ch, col
+            ),
+            GridParseError::Empty => write!(f, "the grid is empty"),
+        }
+    }
+}
+
+impl std::error::Error for GridParseError {}
+
+impl From<GridParseError> for SolverError {
+    fn from(e: GridParseError) -> Self {
+        SolverError::Generic(e.into())
+    }
+}
+
+/// A rectangular grid of cells stored row by row
+#[derive(Debug)]
+pub(super) struct Grid<T> {
+    cells: Vec<T>,
+
+    rows: usize,
+
+    columns: usize,
+}
+
+impl<T> Grid<T> {
+    /// Parse one cell per character with `f`, making sure every line has the same width
+    pub(super) fn from_lines<F>(lines: &[String], f: F) -> Result<Self, GridParseError>
+    where
+        F: Fn(char) -> Option<T>,
+    {
+        let mut cells = Vec::new();
+        let mut columns = None;
+
+        for (row, line) in lines.iter().enumerate() {
+            let width = line.chars().count();
+            let expected = *columns.get_or_insert(width);
+
+            if width != expected {
+                return Err(GridParseError::Ragged {
+                    row: row + 1,
+                    got: width,
+                    expected,
+                });
+            }
+
+            for (col, ch) in line.chars().enumerate() {
+                let cell = f(ch).ok_or(GridParseError::BadChar {
+                    row: row + 1,
+                    col: col + 1,
+                    ch,
+                })?;
+
+                cells.push(cell);
+            }
+        }
+
+        if cells.is_empty() {
+            return Err(GridParseError::Empty);
+        }
+
+        Ok(Grid {
+            cells,
+            rows: lines.len(),
+            columns: columns.unwrap_or(0),
+        })
+    }
+
+    pub(super) fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub(super) fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub(super) fn into_cells(self) -> Vec<T> {
+        self.cells
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn get(&self, row: usize, column: usize) -> Option<&T> {
+        if row < self.rows && column < self.columns {
+            self.cells.get(row * self.columns + column)
+        } else {
+            None
+        }
+    }
+}
+
+/// Replicate `grid` `nx` times horizontally and `ny` times vertically. Each cell of a tile is
+/// computed by `transform` from the original cell and the `(tile_x, tile_y)` position of the tile
+#[allow(dead_code)]
+pub(super) fn tile<T>(
+    grid: &Grid<T>,
+    nx: usize,
+    ny: usize,
+    transform: impl Fn(&T, usize, usize) -> T,
+) -> Grid<T> {
+    let rows = grid.rows * ny;
+    let columns = grid.columns * nx;
+
+    let mut cells = Vec::with_capacity(rows * columns);
+    for row in 0..rows {
+        for column in 0..columns {
+            let cell = &grid.cells[(row % grid.rows) * grid.columns + column % grid.columns];
+            cells.push(transform(cell, column / grid.columns, row / grid.rows));
+        }
+    }
+
+    Grid {
+        cells,
+        rows,
+        columns,
+    }
+}
+
+/// The smallest grid anchored at `(0, 0)` holding every point it has been extended with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct BoundingBox {
+    /// The largest `(x, y)` coordinates seen so far, if any
+    max: Option<(usize, usize)>,
+}
+
+impl BoundingBox {
+    pub(super) fn extend(&mut self, point: (usize, usize)) {
+        self.max = Some(match self.max {
+            Some((x, y)) => (x.max(point.0), y.max(point.1)),
+            None => point,
+        });
+    }
+
+    /// The `(width, height)` of the grid, coordinates starting at 0
+    pub(super) fn dimensions(&self) -> (usize, usize) {
+        self.max.map(|(x, y)| (x + 1, y + 1)).unwrap_or((0, 0))
+    }
+}
+
+/// ANSI escape sequence starting a highlighted cell
+const HIGHLIGHT: &str = "\x1b[1m";
+
+/// ANSI escape sequence ending a highlighted cell
+const RESET: &str = "\x1b[0m";
+
+/// Render a grid of `(rows, columns)` dimensions one row per line. `cell` gives the value of a
+/// `(row, column)` cell and whether it should be highlighted
+pub(super) fn render_ansi<W, T, F>(out: &mut W, dimensions: (usize, usize), cell: F) -> fmt::Result
+where
+    W: fmt::Write,
+    T: fmt::Display,
+    F: Fn(usize, usize) -> (T, bool),
+{
+    for row in 0..dimensions.0 {
+        for column in 0..dimensions.1 {
+            let (value, highlighted) = cell(row, column);
+            if highlighted {
+                write!(out, "{}{}{}", HIGHLIGHT, value, RESET)?;
+            } else {
+                write!(out, "{}", value)?;
+            }
+        }
+
+        out.write_char('\n')?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_reject_negative_neighbor_at_origin() {
+        assert_eq!(checked_neighbor((0, 0), (-1, 0), (3, 3)), None);
+        assert_eq!(checked_neighbor((0, 2), (0, -1), (3, 3)), Some((0, 1)));
+        assert_eq!(checked_neighbor((2, 0), (-1, -1), (3, 3)), None);
+    }
+
+    #[test]
+    fn should_reject_neighbor_past_edge() {
+        assert_eq!(checked_neighbor((2, 2), (1, 0), (3, 3)), None);
+        assert_eq!(checked_neighbor((2, 2), (0, 1), (3, 3)), None);
+        assert_eq!(checked_neighbor((1, 1), (1, 1), (3, 3)), Some((2, 2)));
+    }
+
+    #[test]
+    fn should_only_count_neighbors_inside_grid() {
+        let always = |_| true;
+
+        // Corner, edge and inner cells of a 3x3 grid
+        assert_eq!(count_neighbors((0, 0), (3, 3), false, always), 2);
+        assert_eq!(count_neighbors((0, 0), (3, 3), true, always), 3);
+        assert_eq!(count_neighbors((2, 1), (3, 3), false, always), 3);
+        assert_eq!(count_neighbors((2, 1), (3, 3), true, always), 5);
+        assert_eq!(count_neighbors((1, 1), (3, 3), true, always), 8);
+    }
+
+    #[test]
+    fn should_check_neighbors_with_predicate() {
+        let grid = Grid::from_lines(&lines("219\n398\n985"), |c| c.to_digit(10)).unwrap();
+        let value = |(x, y)| *grid.get(x, y).unwrap();
+        let dimensions = (grid.rows(), grid.columns());
+
+        // The 1 on the top edge is lower than its neighbors, the bottom corner 5 is not
+        assert!(neighbors_satisfy((0, 1), dimensions, false, |p| 1 < value(
+            p
+        )));
+        assert!(!neighbors_satisfy((0, 1), dimensions, true, |p| 9 > value(
+            p
+        )));
+        assert!(!neighbors_satisfy((2, 2), dimensions, false, |p| 5 > value(p)));
+        assert_eq!(
+            count_neighbors((2, 2), dimensions, true, |p| value(p) == 8),
+            2
+        );
+    }
+
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn should_parse_rectangular_grid() {
+        let grid = Grid::from_lines(&lines("123\n456"), |c| c.to_digit(10)).unwrap();
+
+        assert_eq!((grid.rows(), grid.columns()), (2, 3));
+        assert_eq!(grid.into_cells(), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn should_report_ragged_row() {
+        let lines = lines("0123456789\n0123456789\n012345678\n0123456789");
+        let err = Grid::from_lines(&lines, |c| c.to_digit(10)).unwrap_err();
+
+        assert_eq!(
+            err,
+            GridParseError::Ragged {
+                row: 3,
+                got: 9,
+                expected: 10
+            }
+        );
+        assert_eq!(err.to_string(), "row 3 has 9 columns, expected 10");
+    }
+
+    #[test]
+    fn should_report_invalid_cell() {
+        let err = Grid::from_lines(&lines("12\n3x"), |c| c.to_digit(10)).unwrap_err();
+
+        assert_eq!(
+            err,
+            GridParseError::BadChar {
+                row: 2,
+                col: 2,
+                ch: 'x'
+            }
+        );
+        assert_eq!(err.to_string(), "row 2 has an invalid cell 'x' at column 2");
+    }
+
+    #[test]
+    fn should_report_empty_grid() {
+        let err = Grid::from_lines(&[], |c| c.to_digit(10)).unwrap_err();
+        assert_eq!(err, GridParseError::Empty);
+
+        let err = Grid::from_lines(&lines("\n"), |c| c.to_digit(10)).unwrap_err();
+        assert_eq!(err, GridParseError::Empty);
+    }
+
+    #[test]
+    fn should_convert_into_solver_error() {
+        let err = SolverError::from(GridParseError::Empty);
+        assert!(matches!(err, SolverError::Generic(e) if e.to_string() == "the grid is empty"));
+    }
+
+    #[test]
+    fn should_tile_grid_with_transform() {
+        let grid = Grid::from_lines(&lines("12\n34"), |c| c.to_digit(10)).unwrap();
+        let tiled = tile(&grid, 3, 2, |&v, x, y| v + 10 * x as u32 + 100 * y as u32);
+
+        assert_eq!((tiled.rows(), tiled.columns()), (4, 6));
+        assert_eq!(tiled.get(0, 0), Some(&1));
+        assert_eq!(tiled.get(1, 5), Some(&24));
+        assert_eq!(tiled.get(3, 2), Some(&113));
+        assert_eq!(tiled.get(4, 0), None);
+    }
+
+    #[test]
+    fn should_wrap_risk_levels_when_tiling() {
+        let grid = Grid::from_lines(&lines("8"), |c| c.to_digit(10)).unwrap();
+        let tiled = tile(&grid, 5, 1, |&v, x, y| (v - 1 + (x + y) as u32) % 9 + 1);
+
+        assert_eq!(tiled.into_cells(), vec![8, 9, 1, 2, 3]);
+    }
+
+    #[test]
+    fn should_size_bounding_box_from_origin() {
+        let mut bounds = BoundingBox::default();
+        assert_eq!(bounds.dimensions(), (0, 0));
+
+        bounds.extend((0, 0));
+        assert_eq!(bounds.dimensions(), (1, 1));
+
+        for point in [(3, 1), (1, 7), (2, 2)] {
+            bounds.extend(point);
+        }
+        assert_eq!(bounds.dimensions(), (4, 8));
+    }
+
+    #[test]
+    fn should_highlight_cells() {
+        let mut out = String::new();
+        render_ansi(&mut out, (2, 2), |r, c| (r * 2 + c, r == c)).unwrap();
+
+        assert_eq!(out, "\x1b[1m0\x1b[0m1\n2\x1b[1m3\x1b[0m\n");
+    }
+}
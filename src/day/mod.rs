@@ -6,10 +6,17 @@ use std::string::String;
 use std::vec::Vec;
 
 use std::error::Error;
+use std::fmt;
 use std::result::Result;
 
 use std::iter::Iterator;
 
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::capture;
+
 mod day1;
 mod day10;
 mod day11;
@@ -27,19 +34,176 @@ mod day7;
 mod day8;
 mod day9;
 
+mod grid;
+mod parse;
+mod stats;
+
 #[derive(Debug)]
 pub(super) enum SolverError {
     UnknownDay(usize),
+
+    /// The day is registered but its solver has not been written yet
+    Unimplemented(usize),
     InvalidPart(usize),
 
-    InputFile(PathBuf, std::io::Error),
+    /// The input file could not be opened
+    OpenInput {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// The input file could not be read, lines start at 1
+    ReadInput {
+        path: PathBuf,
+        line: usize,
+        source: std::io::Error,
+    },
+
+    Generic(Box<dyn Error + Send + Sync>),
+
+    /// The solver did not answer within the given budget
+    Timeout(Duration),
+
+    Test {
+        got: String,
+        expected: String,
+    },
+
+    /// The solver wrote to stderr during a strict test
+    UnexpectedStderr(String),
+}
+
+pub(super) type SolverResult = Result<String, SolverError>;
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::UnknownDay(day) => write!(f, "no solver registered for day {}", day),
+            SolverError::Unimplemented(day) => write!(f, "day {} is not implemented yet", day),
+            SolverError::InvalidPart(part) => write!(f, "invalid part {}", part),
+            SolverError::OpenInput { path, source } => {
+                write!(f, "failed to read {:?}: {}", path, source)
+            }
+            SolverError::ReadInput { path, line, source } => {
+                write!(f, "failed to read {:?} at line {}: {}", path, line, source)
+            }
+            SolverError::Generic(e) => write!(f, "{}", e),
+            SolverError::Timeout(timeout) => write!(f, "no answer within {:?}", timeout),
+            SolverError::Test { got, expected } => {
+                write!(f, "test failed: got {}, expected {}", got, expected)
+            }
+            SolverError::UnexpectedStderr(stderr) => {
+                write!(f, "unexpected output on stderr: {:?}", stderr)
+            }
+        }
+    }
+}
+
+impl Error for SolverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SolverError::OpenInput { source, .. } | SolverError::ReadInput { source, .. } => {
+                Some(source)
+            }
+            SolverError::Generic(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// A typed view over the answer returned by a solver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Answer {
+    UInt(u64),
+    Int(i64),
+
+    /// A multi-line answer, stored without trailing whitespace or trailing empty rows
+    Grid(Vec<String>),
+
+    Text(String),
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        if s.contains('\n') {
+            let mut rows = s
+                .lines()
+                .map(|l| l.trim_end().to_string())
+                .collect::<Vec<_>>();
+            while rows.last().is_some_and(|r| r.is_empty()) {
+                rows.pop();
+            }
+
+            Answer::Grid(rows)
+        } else if let Ok(value) = s.parse::<u64>() {
+            Answer::UInt(value)
+        } else if let Ok(value) = s.parse::<i64>() {
+            Answer::Int(value)
+        } else {
+            Answer::Text(s.to_string())
+        }
+    }
+}
 
-    Generic(Box<dyn Error>),
+impl Answer {
+    /// The answer on a single line of at most `max_width` characters, for aligned tables: grids
+    /// are shown by their dimensions and long values are truncated with a trailing `…`
+    pub(super) fn summary(&self, max_width: usize) -> String {
+        let summary = match self {
+            Answer::Grid(rows) => {
+                let columns = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+                format!("<grid {}x{}>", columns, rows.len())
+            }
+            answer => answer.to_string(),
+        };
 
-    Test { got: String, expected: String },
+        if summary.chars().count() <= max_width {
+            return summary;
+        }
+
+        let mut truncated = summary
+            .chars()
+            .take(max_width.saturating_sub(1))
+            .collect::<String>();
+        if max_width > 0 {
+            truncated.push('…');
+        }
+
+        truncated
+    }
 }
 
-type SolverResult = Result<String, SolverError>;
+/// Insert a `,` between every group of three digits of `value`, starting from the right
+fn group_digits(value: &str) -> String {
+    let (sign, digits) = match value.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", value),
+    };
+
+    let mut grouped = String::from(sign);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
+
+/// The alternate format, `{:#}`, groups the digits of numeric answers by thousands
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::UInt(value) if f.alternate() => f.write_str(&group_digits(&value.to_string())),
+            Answer::Int(value) if f.alternate() => f.write_str(&group_digits(&value.to_string())),
+            Answer::UInt(value) => write!(f, "{}", value),
+            Answer::Int(value) => write!(f, "{}", value),
+            Answer::Grid(rows) => write!(f, "{}", rows.join("\n")),
+            Answer::Text(text) => f.write_str(text),
+        }
+    }
+}
 
 pub(super) trait Solver {
     fn name(&self) -> &'static str;
@@ -49,12 +213,78 @@ pub(super) trait Solver {
     fn solve_part2(&self, lines: Vec<String>) -> SolverResult;
 
     fn test_expected(&self, part: usize) -> &'static str;
+
+    /// The answer of `part` on the real input, filled in once the day has been solved
+    fn real_expected(&self, _part: usize) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether the solver has been written yet
+    fn implemented(&self) -> bool {
+        true
+    }
+
+    /// The module the solver is defined in, e.g. `day5`
+    fn module(&self) -> &'static str {
+        let path = std::any::type_name::<Self>();
+        let module = path.rsplit_once("::").map_or(path, |(module, _)| module);
+
+        module.rsplit("::").next().unwrap_or(module)
+    }
+
+    /// Adjust the solver to the options given on the command line
+    fn configure(&mut self, _options: &Options) {}
 }
 
-struct PreparedSolver<'a>(Vec<String>, &'a Box<dyn Solver>);
+/// Options given on the command line that solvers may opt into
+#[derive(Debug, Default, Clone)]
+pub(super) struct Options {
+    /// Print intermediate results while solving
+    pub(super) verbose: bool,
 
-pub(super) fn name(day: usize) -> Option<&'static str> {
-    let days: &[Box<dyn Solver>] = &[
+    /// Break results down by category, e.g. Day5 overlaps by line orientation
+    pub(super) breakdown: bool,
+
+    /// Print the final state of grid based puzzles
+    pub(super) show_grid: bool,
+
+    /// Narrate how the answer is computed, e.g. Day2 submarine commands or the Day16 packet tree
+    pub(super) explain: bool,
+
+    /// Also count the diagonals of a Day4 Bingo board as winning lines
+    pub(super) diagonals: bool,
+
+    /// Custom Day10 scoring table, as `corruption/completion` points
+    pub(super) scoring: Option<String>,
+
+    /// Day12 path traversal, `bfs` or `dfs`
+    pub(super) traversal: Option<String>,
+
+    /// Report the progress of long-running solvers on stderr
+    pub(super) progress: bool,
+
+    /// Check that the input matches the assumptions of the puzzle, e.g. one low point per Day9
+    /// basin
+    pub(super) check: bool,
+
+    /// Print the largest Day9 basins with the positions they cover
+    pub(super) show_basins: bool,
+
+    /// Size of the Day1 sliding window, overriding the default of each part
+    pub(super) window: Option<usize>,
+
+    /// Number of simulated Day6 days, overriding the default of each part
+    pub(super) days: Option<usize>,
+
+    /// Number of header lines dropped from the input before solving
+    pub(super) skip_header: usize,
+}
+
+struct PreparedSolver<'a>(Vec<String>, &'a dyn Solver);
+
+/// Every solver, indexed by `day - 1`
+fn registry() -> Vec<Box<dyn Solver>> {
+    vec![
         day1::new(),
         day2::new(),
         day3::new(),
@@ -71,47 +301,221 @@ pub(super) fn name(day: usize) -> Option<&'static str> {
         day14::new(),
         day15::new(),
         day16::new(),
-    ];
+    ]
+}
 
-    days.get(day - 1).map(|d| d.name())
+pub(super) fn name(day: usize) -> Option<&'static str> {
+    registry().get(day.checked_sub(1)?).map(|d| d.name())
 }
 
-fn prepare_solver<P: AsRef<Path>, Fn: FnOnce(PreparedSolver) -> SolverResult>(
-    path: P,
+/// A day of the calendar, as listed by the `list` command
+pub(super) struct DayInfo {
+    pub(super) day: usize,
+    pub(super) name: &'static str,
+    pub(super) parts: usize,
+    pub(super) implemented: bool,
+}
+
+impl DayInfo {
+    pub(super) fn url(&self) -> String {
+        format!("https://adventofcode.com/2021/day/{}", self.day)
+    }
+}
+
+pub(super) fn catalog() -> Vec<DayInfo> {
+    registry()
+        .iter()
+        .enumerate()
+        .map(|(i, solver)| DayInfo {
+            day: i + 1,
+            name: solver.name(),
+            parts: 2,
+            implemented: solver.implemented(),
+        })
+        .collect()
+}
+
+/// Number of days of the Advent of Code calendar
+const CALENDAR_DAYS: usize = 25;
+
+/// A day of the calendar as seen by the registry, as printed by the `debug registry` command
+pub(super) struct RegistryEntry {
+    pub(super) day: usize,
+
+    /// The module and name of the registered solver, `None` if no solver is registered yet
+    pub(super) solver: Option<(&'static str, &'static str)>,
+
+    pub(super) implemented: bool,
+}
+
+impl RegistryEntry {
+    /// Whether the solver registered for the day lives in the module of another day
+    pub(super) fn is_misplaced(&self) -> bool {
+        self.solver
+            .is_some_and(|(module, _)| module != format!("day{}", self.day))
+    }
+}
+
+/// Every day of the calendar, with the solver registered for it if any
+pub(super) fn registry_entries() -> Vec<RegistryEntry> {
+    let registry = registry();
+
+    (1..=CALENDAR_DAYS.max(registry.len()))
+        .map(|day| {
+            let solver = registry.get(day - 1);
+
+            RegistryEntry {
+                day,
+                solver: solver.map(|s| (s.module(), s.name())),
+                implemented: solver.is_some_and(|s| s.implemented()),
+            }
+        })
+        .collect()
+}
+
+/// Called periodically by long-running solvers with the amount of work done so far, e.g. the
+/// number of days simulated
+pub(super) type Progress<'a> = Option<Box<dyn FnMut(usize) + 'a>>;
+
+/// A progress printing a dot on stderr at each report if `enabled`, silent otherwise. The dots
+/// are asked for, so they are not held against the solver by `test --strict`.
+pub(super) fn stderr_progress(enabled: bool) -> Progress<'static> {
+    if enabled {
+        Some(Box::new(|_| capture::write_uncaptured(".")))
+    } else {
+        None
+    }
+}
+
+/// End the line of dots printed by a `stderr_progress` if `enabled`
+pub(super) fn end_stderr_progress(enabled: bool) {
+    if enabled {
+        capture::write_uncaptured("\n");
+    }
+}
+
+/// Whether a test expectation has obviously not been filled in
+fn is_placeholder(expected: &str) -> bool {
+    let expected = expected.trim();
+    expected.is_empty() || expected == "?" || expected.eq_ignore_ascii_case("todo")
+}
+
+/// The `(day, part)` of every implemented solver whose test expectation is a placeholder
+pub(super) fn placeholder_tests() -> Vec<(usize, usize)> {
+    let mut placeholders = Vec::new();
+
+    for (i, solver) in registry().iter().enumerate() {
+        if !solver.implemented() {
+            continue;
+        }
+
+        for part in 1..=2 {
+            if is_placeholder(solver.test_expected(part)) {
+                placeholders.push((i + 1, part));
+            }
+        }
+    }
+
+    placeholders
+}
+
+/// Whether `answer` is the test expectation of `part` of `day`, meaning it was most likely
+/// computed from the sample input
+pub(super) fn is_test_answer(day: usize, part: usize, answer: &str) -> bool {
+    let days = registry();
+
+    match day.checked_sub(1).and_then(|index| days.get(index)) {
+        Some(solver) if (1..=2).contains(&part) => {
+            Answer::from(solver.test_expected(part)) == Answer::from(answer)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `answer` is the known answer of `part` of `day` on the real input, `None` if the
+/// answer is not known yet
+pub(super) fn verify_answer(day: usize, part: usize, answer: &str) -> Option<bool> {
+    let days = registry();
+    let solver = days.get(day.checked_sub(1)?)?;
+    let expected = solver.real_expected(part)?;
+
+    Some(Answer::from(expected) == Answer::from(answer))
+}
+
+/// Where the lines of a puzzle input come from
+#[derive(Debug)]
+enum InputSource {
+    File(PathBuf),
+
+    /// An input given as a single block of text, e.g. read from the clipboard or an archive
+    Text(String),
+}
+
+/// Read the lines of `source`, without their line endings nor a leading byte order mark
+fn read_lines(source: InputSource) -> Result<Vec<String>, SolverError> {
+    match source {
+        InputSource::File(path) => {
+            let file = fs::File::open(&path).map_err(|source| SolverError::OpenInput {
+                path: path.clone(),
+                source,
+            })?;
+
+            lines_of(BufReader::new(file), &path)
+        }
+        InputSource::Text(text) => lines_of(text.as_bytes(), Path::new("<text>")),
+    }
+}
+
+/// Collect the lines of `reader`, reporting read errors as coming from `path`
+fn lines_of<R: BufRead>(reader: R, path: &Path) -> Result<Vec<String>, SolverError> {
+    let mut lines = reader
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            line.map_err(|source| SolverError::ReadInput {
+                path: path.to_path_buf(),
+                line: index + 1,
+                source,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(first) = lines.first_mut() {
+        if let Some(stripped) = first.strip_prefix('\u{feff}') {
+            *first = stripped.to_string();
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Drop the first `n` lines of the input, or all of them if there are fewer
+fn skip_header(mut lines: Vec<String>, n: usize) -> Vec<String> {
+    lines.drain(..n.min(lines.len()));
+    lines
+}
+
+fn prepare_solver<Fn: FnOnce(PreparedSolver) -> SolverResult>(
+    source: InputSource,
     day: usize,
+    options: &Options,
     f: Fn,
 ) -> SolverResult {
-    let days: &[Box<dyn Solver>] = &[
-        day1::new(),
-        day2::new(),
-        day3::new(),
-        day4::new(),
-        day5::new(),
-        day6::new(),
-        day7::new(),
-        day8::new(),
-        day9::new(),
-        day10::new(),
-        day11::new(),
-        day12::new(),
-        day13::new(),
-        day14::new(),
-        day15::new(),
-        day16::new(),
-    ];
+    let lines = skip_header(read_lines(source)?, options.skip_header);
+    let mut days = registry();
 
-    let file = fs::File::open(path.as_ref())
-        .map_err(|e| SolverError::InputFile(PathBuf::from(path.as_ref()), e))?;
+    let solver = day
+        .checked_sub(1)
+        .and_then(|index| days.get_mut(index))
+        .ok_or(SolverError::UnknownDay(day))?;
 
-    let reader = BufReader::new(file);
-    let lines = reader
-        .lines()
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| SolverError::InputFile(PathBuf::from(path.as_ref()), e))?;
+    if !solver.implemented() {
+        return Err(SolverError::Unimplemented(day));
+    }
 
-    days.get(day - 1)
-        .ok_or(SolverError::UnknownDay(day))
-        .and_then(|s| f(PreparedSolver(lines, s)))
+    solver.configure(options);
+
+    f(PreparedSolver(lines, solver.as_ref()))
 }
 
 fn run_solver<'a>(solver: PreparedSolver<'a>, part: usize) -> SolverResult {
@@ -122,19 +526,31 @@ fn run_solver<'a>(solver: PreparedSolver<'a>, part: usize) -> SolverResult {
     }
 }
 
-pub(super) fn solve<P: AsRef<Path>>(path: P, day: usize, part: usize) -> SolverResult {
-    prepare_solver(path, day, |s| run_solver(s, part))
+pub(super) fn solve<P: AsRef<Path>>(
+    path: P,
+    day: usize,
+    part: usize,
+    options: &Options,
+) -> SolverResult {
+    let source = InputSource::File(path.as_ref().to_path_buf());
+    prepare_solver(source, day, options, |s| run_solver(s, part))
 }
 
-fn run_test<'a>(solver: PreparedSolver<'a>, part: usize) -> SolverResult {
-    let expected = solver.1.test_expected(part);
-    let result = if part == 1 {
-        solver.1.solve_part1(solver.0)
-    } else {
-        solver.1.solve_part2(solver.0)
-    }?;
+/// Solve `part` of `day` with an input given as text rather than as a file
+pub(super) fn solve_text(text: &str, day: usize, part: usize, options: &Options) -> SolverResult {
+    let source = InputSource::Text(text.to_string());
+    prepare_solver(source, day, options, |s| run_solver(s, part))
+}
+
+/// Run `part` and compare its answer to `expected`, or to the expectation of the solver if `None`
+fn run_test<'a>(solver: PreparedSolver<'a>, part: usize, expected: Option<&str>) -> SolverResult {
+    let day = solver.1;
 
-    if result == expected {
+    // Only ask for the expectation of a part the solver knows about
+    let result = run_solver(solver, part)?;
+    let expected = expected.unwrap_or_else(|| day.test_expected(part));
+
+    if Answer::from(result.as_str()) == Answer::from(expected) {
         Ok(result)
     } else {
         Err(SolverError::Test {
@@ -144,6 +560,272 @@ fn run_test<'a>(solver: PreparedSolver<'a>, part: usize) -> SolverResult {
     }
 }
 
-pub(super) fn test<P: AsRef<Path>>(path: P, day: usize, part: usize) -> SolverResult {
-    prepare_solver(path, day, |s| run_test(s, part))
+pub(super) fn test<P: AsRef<Path>>(
+    path: P,
+    day: usize,
+    part: usize,
+    expected: Option<&str>,
+    options: &Options,
+) -> SolverResult {
+    let source = InputSource::File(path.as_ref().to_path_buf());
+    prepare_solver(source, day, options, |s| run_test(s, part, expected))
+}
+
+/// Test `part` of `day` with an input given as text rather than as a file
+pub(super) fn test_text(
+    text: &str,
+    day: usize,
+    part: usize,
+    expected: Option<&str>,
+    options: &Options,
+) -> SolverResult {
+    let source = InputSource::Text(text.to_string());
+    prepare_solver(source, day, options, |s| run_test(s, part, expected))
+}
+
+/// Run `f` on a worker thread, giving up once `timeout` has elapsed. Rust threads cannot be
+/// killed, so on timeout the worker is detached and keeps running until it returns on its own,
+/// possibly for as long as the process lives.
+pub(super) fn with_timeout<F>(timeout: Option<Duration>, f: F) -> SolverResult
+where
+    F: FnOnce() -> SolverResult + Send + 'static,
+{
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return f(),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver is gone if we already timed out, nobody is left to care about the result
+        let _ = tx.send(f());
+    });
+
+    rx.recv_timeout(timeout).map_err(|e| match e {
+        mpsc::RecvTimeoutError::Timeout => SolverError::Timeout(timeout),
+        mpsc::RecvTimeoutError::Disconnected => {
+            SolverError::Generic("The solver thread panicked".into())
+        }
+    })?
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct SlowSolver;
+
+    impl Solver for SlowSolver {
+        fn name(&self) -> &'static str {
+            "Slow"
+        }
+
+        fn solve_part1(&self, _lines: Vec<String>) -> SolverResult {
+            thread::sleep(Duration::from_secs(5));
+            Ok("42".to_string())
+        }
+
+        fn solve_part2(&self, _lines: Vec<String>) -> SolverResult {
+            Ok("42".to_string())
+        }
+
+        fn test_expected(&self, _part: usize) -> &'static str {
+            "42"
+        }
+    }
+
+    fn solve_slow(part: usize) -> SolverResult {
+        run_solver(PreparedSolver(Vec::new(), &SlowSolver), part)
+    }
+
+    #[test]
+    fn should_classify_answers() {
+        assert_eq!(Answer::from("37"), Answer::UInt(37));
+        assert_eq!(Answer::from("-4"), Answer::Int(-4));
+        assert_eq!(Answer::from("HGFPB"), Answer::Text("HGFPB".to_string()));
+    }
+
+    #[test]
+    fn should_group_numeric_answer_digits() {
+        assert_eq!(
+            format!("{:#}", Answer::from("26984457539")),
+            "26,984,457,539"
+        );
+        assert_eq!(format!("{:#}", Answer::from("-1234")), "-1,234");
+        assert_eq!(format!("{:#}", Answer::from("168")), "168");
+        assert_eq!(format!("{:#}", Answer::from("RZKZLPGH")), "RZKZLPGH");
+        assert_eq!(format!("{}", Answer::from("26984457539")), "26984457539");
+    }
+
+    #[test]
+    fn should_summarize_answers() {
+        let row = "#".repeat(40);
+        let grid = [row.as_str(); 6].join("\n");
+
+        assert_eq!(Answer::from(grid.as_str()).summary(20), "<grid 40x6>");
+        assert_eq!(Answer::from("1976896901756").summary(20), "1976896901756");
+        assert_eq!(Answer::from("1976896901756").summary(6), "19768…");
+    }
+
+    #[test]
+    fn should_compare_grids_ignoring_trailing_newline() {
+        let grid = "#...#\n.#.#.\n..#..";
+
+        assert_eq!(Answer::from(grid), Answer::from("#...#\n.#.#.\n..#..\n"));
+        assert_eq!(Answer::from(grid), Answer::from("#...# \n.#.#.\n..#..\n\n"));
+        assert_ne!(Answer::from(grid), Answer::from("#...#\n.#.#.\n..#.#"));
+    }
+
+    #[test]
+    fn should_flag_placeholder_tests() {
+        assert!(is_placeholder(""));
+        assert!(is_placeholder(" TODO "));
+        assert!(!is_placeholder("0"));
+
+        assert!(placeholder_tests().is_empty());
+    }
+
+    #[test]
+    fn should_report_line_of_read_error() {
+        let path = std::env::temp_dir().join(format!("aoc2k21-invalid-{}.txt", std::process::id()));
+        fs::write(&path, b"1\n2\n\xff\xfe\n4\n").unwrap();
+
+        let result = read_lines(InputSource::File(path.clone()));
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(SolverError::ReadInput { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn should_display_solver_errors() {
+        let error = SolverError::Test {
+            got: "7".to_string(),
+            expected: "5".to_string(),
+        };
+        assert_eq!(error.to_string(), "test failed: got 7, expected 5");
+
+        let error = SolverError::Generic("Missing lanternfish timers".into());
+        assert_eq!(error.to_string(), "Missing lanternfish timers");
+        assert!(error.source().is_some());
+
+        assert_eq!(
+            SolverError::UnknownDay(26).to_string(),
+            "no solver registered for day 26"
+        );
+    }
+
+    #[test]
+    fn should_report_missing_input() {
+        let result = read_lines(InputSource::File("inputs/does-not-exist.txt".into()));
+        assert!(matches!(result, Err(SolverError::OpenInput { .. })));
+    }
+
+    #[test]
+    fn should_read_lines_of_each_source() {
+        let expected = vec!["199", "200", "208"];
+
+        let path = std::env::temp_dir().join(format!("aoc2k21-crlf-{}.txt", std::process::id()));
+        fs::write(&path, "\u{feff}199\r\n200\r\n208\r\n").unwrap();
+        let result = read_lines(InputSource::File(path.clone()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), expected);
+
+        let text = "\u{feff}199\n200\r\n208".to_string();
+        assert_eq!(read_lines(InputSource::Text(text)).unwrap(), expected);
+    }
+
+    #[test]
+    fn should_time_out_slow_solver() {
+        let timeout = Some(Duration::from_millis(50));
+
+        let result = with_timeout(timeout, || solve_slow(1));
+        assert!(matches!(result, Err(SolverError::Timeout(_))));
+
+        let result = with_timeout(timeout, || solve_slow(2));
+        assert_eq!(result.unwrap(), "42");
+    }
+
+    #[test]
+    fn should_dispatch_to_registered_days() {
+        let options = Options::default();
+
+        assert_eq!(name(1), Some("Sonar Sweep"));
+        assert_eq!(name(0), None);
+        assert_eq!(name(17), None);
+
+        assert_eq!(solve_text("199\n200", 1, 1, &options).unwrap(), "1");
+        assert!(matches!(
+            solve_text("", 0, 1, &options),
+            Err(SolverError::UnknownDay(0))
+        ));
+        assert!(matches!(
+            solve_text("", 15, 1, &options),
+            Err(SolverError::Unimplemented(15))
+        ));
+        assert!(matches!(
+            solve_text("", 17, 1, &options),
+            Err(SolverError::UnknownDay(17))
+        ));
+    }
+
+    #[test]
+    fn should_verify_answers_against_real_expectations() {
+        assert_eq!(verify_answer(1, 1, "1688"), Some(true));
+        assert_eq!(verify_answer(1, 2, "1728"), Some(true));
+        assert_eq!(verify_answer(1, 1, "7"), Some(false));
+
+        assert_eq!(verify_answer(2, 1, "1690000"), None);
+        assert_eq!(verify_answer(0, 1, "1688"), None);
+        assert_eq!(verify_answer(1, 3, "1688"), None);
+    }
+
+    #[test]
+    fn should_map_registered_days_to_their_module() {
+        let entries = registry_entries();
+
+        assert_eq!(entries.len(), CALENDAR_DAYS);
+        assert!(entries.iter().all(|entry| !entry.is_misplaced()));
+
+        assert_eq!(entries[4].solver.map(|(module, _)| module), Some("day5"));
+        assert!(!entries[14].implemented);
+        assert!(entries[16].solver.is_none());
+    }
+
+    #[test]
+    fn should_test_each_part_against_its_expectation() {
+        let options = Options::default();
+        let sample = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+
+        assert_eq!(test_text(sample, 1, 1, None, &options).unwrap(), "7");
+        assert_eq!(test_text(sample, 1, 2, None, &options).unwrap(), "5");
+        assert!(matches!(
+            test_text(sample, 1, 3, None, &options),
+            Err(SolverError::InvalidPart(3))
+        ));
+
+        assert_eq!(test_text("1\n2", 1, 1, Some("1"), &options).unwrap(), "1");
+        assert!(matches!(
+            test_text(sample, 1, 1, Some("1"), &options),
+            Err(SolverError::Test { .. })
+        ));
+    }
+
+    #[test]
+    fn should_skip_header_lines() {
+        let text = "# depths\n# from the sonar\n199\n200".to_string();
+        let lines = read_lines(InputSource::Text(text)).unwrap();
+
+        assert_eq!(skip_header(lines.clone(), 2), vec!["199", "200"]);
+        assert!(skip_header(lines, 5).is_empty());
+
+        let options = Options {
+            skip_header: 2,
+            ..Options::default()
+        };
+        let sample = "# depths\n# from the sonar\n199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+        assert_eq!(solve_text(sample, 1, 1, &options).unwrap(), "7");
+    }
 }
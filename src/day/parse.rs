@@ -0,0 +1,119 @@
+use super::SolverError;
+
+use std::iter::Peekable;
+use std::slice::Iter;
+
+/// Split `line` on any of the `separators`, dropping empty tokens
+pub(super) fn tokens<'a>(line: &'a str, separators: &[char]) -> Vec<&'a str> {
+    line.split(|c| separators.contains(&c))
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Split `s` exactly once on `sep`, failing if the separator or any of the two halves is missing
+pub(super) fn parse_pair<'a>(s: &'a str, sep: &str) -> Result<(&'a str, &'a str), SolverError> {
+    let (left, right) = s
+        .split_once(sep)
+        .ok_or_else(|| SolverError::Generic(format!("Missing `{}` in {:?}", sep, s).into()))?;
+
+    if left.is_empty() || right.is_empty() {
+        return Err(SolverError::Generic(
+            format!("Missing half around `{}` in {:?}", sep, s).into(),
+        ));
+    }
+
+    Ok((left, right))
+}
+
+/// A parser for inputs made of multiple sections separated by blank lines
+pub(super) struct SectionParser<'a> {
+    lines: Peekable<Iter<'a, String>>,
+}
+
+impl<'a> SectionParser<'a> {
+    pub(super) fn new(lines: &'a [String]) -> Self {
+        SectionParser {
+            lines: lines.iter().peekable(),
+        }
+    }
+
+    /// Parse the next section with `f`, or fail if there is no `name` section left
+    pub(super) fn section<T, F>(&mut self, name: &str, f: F) -> Result<T, SolverError>
+    where
+        F: FnOnce(Vec<&'a str>) -> Result<T, SolverError>,
+    {
+        let section = self
+            .next()
+            .ok_or_else(|| SolverError::Generic(format!("Missing {} section", name).into()))?;
+
+        f(section)
+    }
+}
+
+impl<'a> Iterator for SectionParser<'a> {
+    type Item = Vec<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip the blank lines separating two sections
+        while self.lines.next_if(|l| l.is_empty()).is_some() {}
+
+        self.lines.peek()?;
+
+        let mut section = Vec::new();
+        while let Some(line) = self.lines.next_if(|l| !l.is_empty()) {
+            section.push(line.as_str());
+        }
+
+        Some(section)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn should_split_on_mixed_separators() {
+        assert_eq!(
+            tokens("  be cfbegad |cefdb, fdgacbe;;  ", &[' ', '|', ',', ';']),
+            vec!["be", "cfbegad", "cefdb", "fdgacbe"]
+        );
+        assert!(tokens(" , ", &[' ', ',']).is_empty());
+    }
+
+    #[test]
+    fn should_parse_pair() {
+        assert_eq!(parse_pair("CH -> B", " -> ").unwrap(), ("CH", "B"));
+        assert_eq!(parse_pair("0,9", ",").unwrap(), ("0", "9"));
+        assert!(parse_pair("CH B", " -> ").is_err());
+        assert!(parse_pair("CH -> ", " -> ").is_err());
+    }
+
+    #[test]
+    fn should_parse_three_sections() {
+        let lines = lines("a\nb\n\nc\n\n\nd\ne\nf");
+        let mut parser = SectionParser::new(&lines);
+
+        let first = parser.section("first", |s| Ok(s.len())).unwrap();
+        let second = parser.section("second", |s| Ok(s.join(","))).unwrap();
+        let third = parser.section("third", Ok).unwrap();
+
+        assert_eq!(first, 2);
+        assert_eq!(second, "c");
+        assert_eq!(third, vec!["d", "e", "f"]);
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn should_fail_on_missing_section() {
+        let lines = lines("a\n\n");
+        let mut parser = SectionParser::new(&lines);
+
+        assert!(parser.section("first", |_| Ok(())).is_ok());
+        assert!(parser.section("second", |_| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+/// Find the input in `range` for which `f` is the smallest, along with that value.
+/// Ties are resolved in favor of the first input, and an empty range gives `None`
+pub(super) fn argmin<T: Ord>(range: Range<usize>, f: impl Fn(usize) -> T) -> Option<(usize, T)> {
+    range
+        .map(|i| (i, f(i)))
+        .fold(None, |best, (i, value)| match best {
+            Some((_, ref best_value)) if *best_value <= value => best,
+            _ => Some((i, value)),
+        })
+}
+
+/// Find the input in `range` for which `f` is the largest, along with that value.
+/// Ties are resolved in favor of the first input, and an empty range gives `None`
+#[allow(dead_code)]
+pub(super) fn argmax<T: Ord>(range: Range<usize>, f: impl Fn(usize) -> T) -> Option<(usize, T)> {
+    range
+        .map(|i| (i, f(i)))
+        .fold(None, |best, (i, value)| match best {
+            Some((_, ref best_value)) if *best_value >= value => best,
+            _ => Some((i, value)),
+        })
+}
+
+/// Find the median of `values` in linear time, reordering them in the process. For an even
+/// number of values this is the upper of the two middle values
+pub(super) fn median<T: Ord + Copy>(values: &mut [T]) -> Option<T> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let middle = values.len() / 2;
+    let (_, median, _) = values.select_nth_unstable(middle);
+
+    Some(*median)
+}
+
+/// Count occurrences of values
+#[derive(Debug, Clone, Default)]
+pub(super) struct Counter<T: Eq + Hash>(HashMap<T, u64>);
+
+impl<T: Eq + Hash + Ord> Counter<T> {
+    pub(super) fn new() -> Self {
+        Counter(HashMap::new())
+    }
+
+    /// Count `value` `count` more times
+    pub(super) fn add(&mut self, value: T, count: u64) {
+        *self.0.entry(value).or_insert(0) += count;
+    }
+
+    /// Count every value of `values` once more
+    pub(super) fn extend(&mut self, values: impl IntoIterator<Item = T>) {
+        for value in values {
+            self.add(value, 1);
+        }
+    }
+
+    /// The value counted the most times, along with its count. Ties are resolved in favor of the
+    /// smallest value, and an empty counter gives `None`
+    pub(super) fn most_common(&self) -> Option<(&T, u64)> {
+        self.0
+            .iter()
+            .map(|(value, &count)| (value, count))
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+    }
+
+    /// The value counted the least times, along with its count. Ties are resolved in favor of the
+    /// smallest value, and an empty counter gives `None`
+    pub(super) fn least_common(&self) -> Option<(&T, u64)> {
+        self.0
+            .iter()
+            .map(|(value, &count)| (value, count))
+            .min_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(b.0)))
+    }
+}
+
+/// A small linear congruential generator, good enough to shuffle test data below `bound`
+#[cfg(test)]
+pub(super) fn pseudo_random(seed: u64, count: usize, bound: u64) -> Vec<u64> {
+    let mut state = seed;
+
+    (0..count)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_find_extremum_of_convex_function() {
+        let parabola = |x: usize| (x as i64 - 4).pow(2);
+
+        assert_eq!(argmin(0..10, parabola), Some((4, 0)));
+        assert_eq!(argmax(0..10, parabola), Some((9, 25)));
+    }
+
+    #[test]
+    fn should_pick_first_input_of_flat_function() {
+        assert_eq!(argmin(3..8, |_| 7), Some((3, 7)));
+        assert_eq!(argmax(3..8, |_| 7), Some((3, 7)));
+        assert_eq!(argmin(0..0, |x| x), None);
+    }
+
+    #[test]
+    fn should_match_sorted_median_on_random_data() {
+        for (seed, count) in [(1, 1), (7, 2), (42, 999), (1337, 1000)] {
+            let mut values = pseudo_random(seed, count, 2000);
+
+            let mut sorted = values.clone();
+            sorted.sort_unstable();
+
+            assert_eq!(median(&mut values), Some(sorted[count / 2]));
+        }
+
+        assert_eq!(median::<u64>(&mut []), None);
+    }
+
+    #[test]
+    fn should_find_most_and_least_common() {
+        let mut counter = Counter::new();
+        counter.extend("NBCCNBBBCBHCB".chars());
+        counter.add('H', 10);
+
+        assert_eq!(counter.most_common(), Some((&'H', 11)));
+        assert_eq!(counter.least_common(), Some((&'N', 2)));
+    }
+
+    #[test]
+    fn should_pick_smallest_value_of_ties() {
+        let mut counter = Counter::new();
+        counter.extend(['c', 'a', 'b', 'b', 'c', 'a']);
+
+        assert_eq!(counter.most_common(), Some((&'a', 2)));
+        assert_eq!(counter.least_common(), Some((&'a', 2)));
+    }
+
+    #[test]
+    fn should_have_no_extremum_when_empty() {
+        let counter = Counter::<char>::new();
+
+        assert_eq!(counter.most_common(), None);
+        assert_eq!(counter.least_common(), None);
+    }
+}
@@ -1,12 +1,101 @@
+mod alloc;
+mod archive;
+mod capture;
+mod clipboard;
 mod cmd;
 mod day;
+mod report;
+mod watch;
 
 use cmd::Command;
 
-const INPUT_PREFIX: &'static str = "inputs";
+use std::error::Error;
+use std::fmt;
+
+const INPUT_PREFIX: &str = "inputs";
+
+#[cfg(feature = "profile-alloc")]
+#[global_allocator]
+static GLOBAL: alloc::CountingAllocator = alloc::CountingAllocator;
+
+/// Any error the binary can fail with, from parsing the command line down to a solver
+#[derive(Debug)]
+enum AocError {
+    Command(cmd::Error),
+    Solver(day::SolverError),
+}
+
+impl From<cmd::Error> for AocError {
+    fn from(e: cmd::Error) -> Self {
+        AocError::Command(e)
+    }
+}
+
+impl From<day::SolverError> for AocError {
+    fn from(e: day::SolverError) -> Self {
+        AocError::Solver(e)
+    }
+}
+
+impl fmt::Display for AocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AocError::Command(e) => write!(f, "{}", e),
+            AocError::Solver(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for AocError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AocError::Command(e) => Some(e),
+            AocError::Solver(e) => Some(e),
+        }
+    }
+}
+
+fn run() -> Result<(), AocError> {
+    let command = Command::parse_from_args()?;
+    command.run(INPUT_PREFIX)?;
+
+    Ok(())
+}
 
 fn main() {
-    Command::parse_from_args()
-        .and_then(|c| c.run(INPUT_PREFIX))
-        .expect("Failed to handle command");
+    if let Err(e) = run() {
+        eprintln!("Failed to handle command: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_convert_command_and_solver_errors() {
+        fn fail_command() -> Result<(), AocError> {
+            Err(cmd::Error::MissingCommand)?
+        }
+
+        fn fail_solver() -> Result<(), AocError> {
+            Err(day::SolverError::InvalidPart(3))?
+        }
+
+        let error = fail_command().unwrap_err();
+        assert!(matches!(
+            error,
+            AocError::Command(cmd::Error::MissingCommand)
+        ));
+        assert_eq!(error.to_string(), "missing command");
+        assert!(error.source().is_some());
+
+        let error = fail_solver().unwrap_err();
+        assert!(matches!(
+            error,
+            AocError::Solver(day::SolverError::InvalidPart(3))
+        ));
+        assert_eq!(error.to_string(), "invalid part 3");
+    }
 }
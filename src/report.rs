@@ -0,0 +1,362 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::day::{self, DayInfo};
+
+/// A single solved part of a day, as rendered in the report
+pub(super) struct ReportRow {
+    pub(super) day: usize,
+    pub(super) name: &'static str,
+    pub(super) part: usize,
+    pub(super) answer: String,
+    pub(super) elapsed: Duration,
+}
+
+/// Escape `s` so that it can be used as a single cell of a markdown table
+fn escape_cell(s: &str) -> String {
+    s.trim_end()
+        .replace('|', "\\|")
+        .lines()
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+/// Render the `rows` as a markdown document with a single table
+pub(super) fn render_markdown(rows: &[ReportRow]) -> String {
+    let mut report = String::new();
+
+    report.push_str("# Advent of Code 2021\n\n");
+    report.push_str("| Day | Name | Part | Answer | Time |\n");
+    report.push_str("|----:|------|-----:|--------|-----:|\n");
+
+    for row in rows {
+        // Writing to a `String` can not fail
+        let _ = writeln!(
+            report,
+            "| {} | {} | {} | {} | {:?} |",
+            row.day,
+            escape_cell(row.name),
+            row.part,
+            escape_cell(&row.answer),
+            row.elapsed
+        );
+    }
+
+    report
+}
+
+/// The widest answer shown in a summary table, longer ones being truncated
+const SUMMARY_ANSWER_WIDTH: usize = 24;
+
+/// Render the `rows` as an aligned plain text table, each answer summarized on a single line
+pub(super) fn render_summary(rows: &[ReportRow]) -> String {
+    let answers = rows
+        .iter()
+        .map(|row| day::Answer::from(row.answer.as_str()).summary(SUMMARY_ANSWER_WIDTH))
+        .collect::<Vec<_>>();
+
+    let name_width = rows
+        .iter()
+        .map(|row| row.name.chars().count())
+        .fold("Name".len(), usize::max);
+    let answer_width = answers
+        .iter()
+        .map(|answer| answer.chars().count())
+        .fold("Answer".len(), usize::max);
+
+    let mut summary = String::new();
+
+    // Writing to a `String` can not fail
+    let _ = writeln!(
+        summary,
+        "Day  {:<name_width$}  Part  {:<answer_width$}  Time",
+        "Name", "Answer"
+    );
+    for (row, answer) in rows.iter().zip(&answers) {
+        let _ = writeln!(
+            summary,
+            "{:>3}  {:<name_width$}  {:>4}  {:<answer_width$}  {:?}",
+            row.day, row.name, row.part, answer, row.elapsed
+        );
+    }
+
+    summary
+}
+
+/// Escape `s` so that it can be used as a JSON string, without the surrounding quotes
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Render the `days` as a JSON array of objects, one per day
+pub(super) fn render_catalog_json(days: &[DayInfo]) -> String {
+    let entries = days
+        .iter()
+        .map(|d| {
+            format!(
+                "{{\"day\":{},\"name\":\"{}\",\"parts\":{},\"url\":\"{}\",\"implemented\":{}}}",
+                d.day,
+                escape_json(d.name),
+                d.parts,
+                escape_json(&d.url()),
+                d.implemented
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]", entries.join(","))
+}
+
+/// Turn `s` into a shell variable name: uppercase letters, digits and underscores
+fn env_key(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Quote `s` for a shell, unless it is only made of characters that never need quoting
+fn env_value(s: &str) -> String {
+    let is_safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+    if is_safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+/// Render the `rows` as `KEY=value` lines that can be evaluated by a shell
+pub(super) fn render_env(rows: &[ReportRow]) -> String {
+    let mut env = String::new();
+
+    for row in rows {
+        let key = env_key(&format!("day{}_part{}", row.day, row.part));
+        // Writing to a `String` can not fail
+        let _ = writeln!(env, "{}={}", key, env_value(&row.answer));
+    }
+
+    env
+}
+
+/// Render the `rows` as one JSON object per day and per line, e.g.
+/// `{"day":1,"name":"Sonar Sweep","part1":"7","part2":"5"}`, rows of a day being contiguous
+pub(super) fn render_json(rows: &[ReportRow]) -> String {
+    let mut json = String::new();
+
+    for day_rows in rows.chunk_by(|a, b| a.day == b.day) {
+        let day = &day_rows[0];
+        let _ = write!(
+            json,
+            "{{\"day\":{},\"name\":\"{}\"",
+            day.day,
+            escape_json(day.name)
+        );
+        for row in day_rows {
+            let _ = write!(
+                json,
+                ",\"part{}\":\"{}\"",
+                row.part,
+                escape_json(&row.answer)
+            );
+        }
+        json.push_str("}\n");
+    }
+
+    json
+}
+
+/// Format `secs` seconds since the Unix epoch as a UTC `YYYY-MM-DDTHH:MM` timestamp
+pub(super) fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let minutes = secs % 86400 / 60;
+
+    // Convert days to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// Render the `rows` as one `timestamp dayN part1=answer part2=answer` line per day, rows of a
+/// same day being next to each other
+pub(super) fn render_log(timestamp: &str, rows: &[ReportRow]) -> String {
+    let mut log = String::new();
+
+    for day_rows in rows.chunk_by(|a, b| a.day == b.day) {
+        let _ = write!(log, "{} day{}", timestamp, day_rows[0].day);
+        for row in day_rows {
+            // Keep multi-line answers on a single line
+            let _ = write!(log, " part{}={}", row.part, row.answer.replace('\n', "\\n"));
+        }
+        log.push('\n');
+    }
+
+    log
+}
+
+/// A row of the reports answered in 2ms, shared by the tests
+#[cfg(test)]
+pub(super) fn test_row(day: usize, name: &'static str, part: usize, answer: &str) -> ReportRow {
+    ReportRow {
+        day,
+        name,
+        part,
+        answer: answer.to_string(),
+        elapsed: Duration::from_millis(2),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_escape_grid_answers() {
+        assert_eq!(escape_cell("#.|\n.#|\n"), "#.\\|<br>.#\\|");
+    }
+
+    #[test]
+    fn should_render_markdown_table() {
+        let rows = [test_row(7, "The Treachery of Whales", 1, "37")];
+
+        let report = render_markdown(&rows);
+        assert!(report.ends_with("| 7 | The Treachery of Whales | 1 | 37 | 2ms |\n"));
+    }
+
+    #[test]
+    fn should_render_aligned_summary() {
+        let rows = [
+            test_row(1, "Sonar Sweep", 1, "7"),
+            test_row(13, "Transparent Origami", 2, "#..\n.##"),
+        ];
+
+        assert_eq!(
+            render_summary(&rows),
+            "Day  Name                 Part  Answer      Time\n  \
+               1  Sonar Sweep             1  7           2ms\n \
+              13  Transparent Origami     2  <grid 3x2>  2ms\n"
+        );
+    }
+
+    #[test]
+    fn should_escape_json_strings() {
+        assert_eq!(escape_json("a \"b\"\\c\n"), "a \\\"b\\\"\\\\c\\n");
+    }
+
+    #[test]
+    fn should_render_catalog_json() {
+        let days = [
+            DayInfo {
+                day: 1,
+                name: "Sonar Sweep",
+                parts: 2,
+                implemented: true,
+            },
+            DayInfo {
+                day: 15,
+                name: "Chiton",
+                parts: 2,
+                implemented: false,
+            },
+        ];
+
+        assert_eq!(
+            render_catalog_json(&days),
+            "[{\"day\":1,\"name\":\"Sonar Sweep\",\"parts\":2,\"url\":\"https://adventofcode.com/2021/day/1\",\"implemented\":true},\
+             {\"day\":15,\"name\":\"Chiton\",\"parts\":2,\"url\":\"https://adventofcode.com/2021/day/15\",\"implemented\":false}]"
+        );
+    }
+
+    #[test]
+    fn should_render_json_per_day() {
+        let rows = [
+            test_row(1, "Sonar Sweep", 1, "7"),
+            test_row(1, "Sonar Sweep", 2, "5"),
+            test_row(13, "Transparent Origami", 2, "#.\n.#"),
+        ];
+
+        assert_eq!(
+            render_json(&rows),
+            "{\"day\":1,\"name\":\"Sonar Sweep\",\"part1\":\"7\",\"part2\":\"5\"}\n\
+             {\"day\":13,\"name\":\"Transparent Origami\",\"part2\":\"#.\\n.#\"}\n"
+        );
+    }
+
+    #[test]
+    fn should_render_env_lines() {
+        let row = |part, answer| test_row(7, "The Treachery of Whales", part, answer);
+
+        let env = render_env(&[row(1, "37"), row(2, "168")]);
+        assert_eq!(env, "DAY7_PART1=37\nDAY7_PART2=168\n");
+    }
+
+    #[test]
+    fn should_quote_env_values() {
+        assert_eq!(env_key("day-13.part 2"), "DAY_13_PART_2");
+        assert_eq!(env_value("#.\n.#"), "'#.\n.#'");
+        assert_eq!(env_value("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn should_format_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00");
+        assert_eq!(format_timestamp(1638871200), "2021-12-07T10:00");
+        assert_eq!(format_timestamp(951782400 + 86399), "2000-02-29T23:59");
+    }
+
+    #[test]
+    fn should_render_log_line_per_day() {
+        let row = |day, part, answer: &str| ReportRow {
+            day,
+            name: "",
+            part,
+            answer: answer.to_string(),
+            elapsed: Duration::from_millis(2),
+        };
+
+        let log = render_log(
+            "2021-12-07T10:00",
+            &[row(7, 1, "37"), row(7, 2, "168"), row(13, 2, "#.\n.#")],
+        );
+        assert_eq!(
+            log,
+            "2021-12-07T10:00 day7 part1=37 part2=168\n2021-12-07T10:00 day13 part2=#.\\n.#\n"
+        );
+    }
+}
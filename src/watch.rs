@@ -0,0 +1,88 @@
+//! Source watching for `--watch`
+//!
+//! The file watcher is only built with the `watch` feature. Saving a file usually triggers a burst
+//! of events, so they are debounced before running the tests again.
+
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+/// Whether sources can be watched in this build
+pub(super) const ENABLED: bool = cfg!(feature = "watch");
+
+/// How long to wait for the end of a burst of events before acting on it
+#[cfg(feature = "watch")]
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Swallow the events received until none arrived for `window`, returning how many there were
+#[cfg_attr(not(feature = "watch"), allow(dead_code))]
+fn drain_burst<T>(rx: &Receiver<T>, window: Duration) -> usize {
+    let mut count = 0;
+    while rx.recv_timeout(window).is_ok() {
+        count += 1;
+    }
+
+    count
+}
+
+/// Call `on_change` once, then again every time `file` is written to, until interrupted
+#[cfg(feature = "watch")]
+pub(super) fn watch(file: &Path, mut on_change: impl FnMut()) -> Result<(), String> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+
+    // Editors often replace the file when saving, so watch its directory instead
+    let directory = file
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    watcher
+        .watch(directory, RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    on_change();
+
+    while let Ok(event) = rx.recv() {
+        let event = event.map_err(|e| e.to_string())?;
+
+        let is_write = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+        let is_file = event
+            .paths
+            .iter()
+            .any(|p| p.file_name() == file.file_name());
+        if !is_write || !is_file {
+            continue;
+        }
+
+        drain_burst(&rx, DEBOUNCE);
+        on_change();
+    }
+
+    Ok(())
+}
+
+/// Call `on_change` once, then again every time `file` is written to, until interrupted
+#[cfg(not(feature = "watch"))]
+pub(super) fn watch(_file: &Path, _on_change: impl FnMut()) -> Result<(), String> {
+    Err("built without the watch feature".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    #[test]
+    fn should_drain_burst_of_events() {
+        let (tx, rx) = mpsc::channel();
+        for i in 0..3 {
+            tx.send(i).unwrap();
+        }
+
+        assert_eq!(drain_burst(&rx, Duration::from_millis(10)), 3);
+        assert_eq!(drain_burst(&rx, Duration::from_millis(10)), 0);
+    }
+}
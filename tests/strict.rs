@@ -0,0 +1,14 @@
+use std::process::Command;
+
+#[test]
+fn clean_day_should_not_write_to_stderr() {
+    let output = Command::new(env!("CARGO_BIN_EXE_aoc2k21"))
+        .args(["test", "day1", "--strict"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Failed to run aoc2k21");
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("[OK]"));
+}
@@ -0,0 +1,22 @@
+use std::process::Command;
+
+#[test]
+fn time_only_should_print_only_duration_tokens() {
+    let output = Command::new(env!("CARGO_BIN_EXE_aoc2k21"))
+        .args(["solve", "day1", "--time-only"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Failed to run aoc2k21");
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+
+    for line in lines {
+        let digits = line.strip_suffix("us").expect("Missing duration unit");
+        assert!(!digits.is_empty());
+        assert!(digits.chars().all(|c| c.is_ascii_digit()));
+    }
+}